@@ -0,0 +1,22 @@
+// Fuzz the day 01 parser and solver with arbitrary input
+#![no_main]
+#![allow(dead_code)]
+
+use libfuzzer_sys::fuzz_target;
+
+include!("../../src/bin/day01.rs");
+
+fuzz_target!(|data: &[u8]| {
+    // Keep each run short
+    if data.len() > 4096 {
+        return;
+    }
+    let Ok(s) = std::str::from_utf8(data) else {
+        return;
+    };
+    let (columns, _) = parse(s);
+    // n^2 pairs, keep n small
+    if columns.len() <= 16 {
+        matrices(&columns);
+    }
+});
@@ -0,0 +1,25 @@
+// Fuzz the day 02 parser and solver with arbitrary input
+#![no_main]
+#![allow(dead_code)]
+
+use libfuzzer_sys::fuzz_target;
+
+include!("../../src/bin/day02.rs");
+
+fuzz_target!(|data: &[u8]| {
+    // Keep each run short
+    if data.len() > 4096 {
+        return;
+    }
+    let Ok(s) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(reports) = parse_reports(s) else {
+        return;
+    };
+    let rules = SafetyRules::default();
+    for report in &reports {
+        report.is_safe(&rules);
+        report.dampen(&rules, 2);
+    }
+});
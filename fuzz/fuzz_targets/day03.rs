@@ -0,0 +1,17 @@
+// Fuzz the day 03 parser and solver with arbitrary input
+#![no_main]
+#![allow(dead_code)]
+
+use libfuzzer_sys::fuzz_target;
+
+include!("../../src/bin/day03.rs");
+
+fuzz_target!(|data: &[u8]| {
+    // Keep each run short
+    if data.len() > 4096 {
+        return;
+    }
+    // Any bytes, and a small buffer so instructions are cut between reads
+    let _ = solve_stream(data, 16, |_| {});
+    annotate(data, true);
+});
@@ -0,0 +1,26 @@
+// Fuzz the day 04 parser and solver with arbitrary input
+#![no_main]
+#![allow(dead_code)]
+
+use libfuzzer_sys::fuzz_target;
+
+include!("../../src/bin/day04.rs");
+
+fuzz_target!(|data: &[u8]| {
+    // Keep each run short
+    if data.len() > 4096 {
+        return;
+    }
+    let Ok(s) = std::str::from_utf8(data) else {
+        return;
+    };
+    let board = Board::parse(s);
+    count_xmas(&board);
+    let _ = board.crosses("MAS", Cross::X);
+    board.cells_of("XMAS");
+    let _ = board.crosses("SAMAS", Cross::Both);
+    let torus = board.crop(1, 1, 8, 80).torus();
+    count_xmas(&torus);
+    torus.cells_of("XMAS");
+    let _ = torus.crosses("SAMAS", Cross::Both);
+});
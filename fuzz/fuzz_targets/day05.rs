@@ -0,0 +1,21 @@
+// Fuzz the day 05 parser and solver with arbitrary input
+#![no_main]
+#![allow(dead_code)]
+
+use libfuzzer_sys::fuzz_target;
+
+include!("../../src/bin/day05.rs");
+
+fuzz_target!(|data: &[u8]| {
+    // Keep each run short
+    if data.len() > 4096 {
+        return;
+    }
+    let Ok(s) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok((rules, updates)) = parse_data(s) else {
+        return;
+    };
+    solve(&rules, &updates);
+});
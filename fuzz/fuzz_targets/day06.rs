@@ -0,0 +1,22 @@
+// Fuzz the day 06 parser and solver with arbitrary input
+#![no_main]
+#![allow(dead_code)]
+
+use libfuzzer_sys::fuzz_target;
+
+include!("../../src/bin/day06.rs");
+
+fuzz_target!(|data: &[u8]| {
+    // Keep each run short
+    if data.len() > 1024 {
+        return;
+    }
+    let Ok(s) = std::str::from_utf8(data) else {
+        return;
+    };
+    let map = Map::parse(s);
+    if let Some(guard) = map.find_guard() {
+        patrol(guard, &map);
+    }
+    map.time_loop();
+});
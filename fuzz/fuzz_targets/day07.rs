@@ -0,0 +1,24 @@
+// Fuzz the day 07 parser and solver with arbitrary input
+#![no_main]
+#![allow(dead_code)]
+
+use libfuzzer_sys::fuzz_target;
+
+include!("../../src/bin/day07.rs");
+
+fuzz_target!(|data: &[u8]| {
+    // Keep each run short
+    if data.len() > 4096 {
+        return;
+    }
+    let Ok(s) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(equations) = parse_data(s) else {
+        return;
+    };
+    // 3^n operator combinations, keep n small
+    for equation in equations.iter().filter(|eq| eq.numbers.len() <= 10) {
+        equation.solve();
+    }
+});
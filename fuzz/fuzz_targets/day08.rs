@@ -0,0 +1,22 @@
+// Fuzz the day 08 parser and solver with arbitrary input
+#![no_main]
+#![allow(dead_code)]
+
+use libfuzzer_sys::fuzz_target;
+
+include!("../../src/bin/day08.rs");
+
+fuzz_target!(|data: &[u8]| {
+    // Keep each run short
+    if data.len() > 4096 {
+        return;
+    }
+    let Ok(s) = std::str::from_utf8(data) else {
+        return;
+    };
+    let (map, antennas) = parse(s);
+    let bounds = map.bounds();
+    let groups = Groups::new(antennas);
+    groups.antinodes(&bounds);
+    groups.check(&bounds);
+});
@@ -1,69 +1,383 @@
 // https://adventofcode.com/2024/day/1
 //
 // Part 1
-// - load the input file in two vectors
+// - load the input file in one vector per column
 // - sort
 // - pair and loop to find the distance between the numbers
 // - sum the distances
 // Part 2
-// - count how many times each element in A appears in B
+// - count how many times each element appears in A and in B
 // - calculate simmiliarity by multipying sum(A.element * count)
+// Any number of columns
+// - the most common number of fields on a line decides how many columns
+//   there are, so a stray header or extra field does not
+// - both scores are computed for every pair of columns, as a matrix
+// - lines with a different number of columns are reported, not used
+// External memory (`--external [--run-size N]`, two columns only)
+// - each column is sorted in runs spilled to temporary files
+// - one merge of both columns gives the two scores: similarity groups equal
+//   values, distance adds up |count_a(t) - count_b(t)| between values
+// - lines are read into one reused buffer of at most `MAX_LINE` bytes, and
+//   malformed lines are reported as they are found, not collected
 
+use aoc2024::counter::Counter;
+use aoc2024::extsort::{self, Sorter};
+use aoc2024::{flag, option};
 use core::panic;
-use std::{fs::File, io::Read, path::Path};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+};
 
-fn main() {
-    // load
-    let input_f = Path::new("inputs/input01.txt");
-    let mut file = match File::open(input_f) {
-        Ok(file) => file,
-        Err(e) => panic!("could not open file {}: {}!", input_f.display(), e),
-    };
+/// Longest line `stream` reads, longer ones are malformed
+const MAX_LINE: usize = 1024;
 
-    let mut list_a: Vec<i32> = vec![];
-    let mut list_b: Vec<i32> = vec![];
+/// Columns of the input, and a message for every malformed line
+fn parse(s: &str) -> (Vec<Vec<i32>>, Vec<String>) {
+    let fields: Counter<usize> = s
+        .split("\n")
+        .map(|l| l.split_whitespace().count())
+        .filter(|&n| n > 0)
+        .collect();
+    let Some(&(&expected, _)) = fields.most_common(1).first() else {
+        return (vec![], vec![]);
+    };
+    let mut columns: Vec<Vec<i32>> = vec![vec![]; expected];
+    let mut malformed = vec![];
 
-    let mut s = String::new();
-    match file.read_to_string(&mut s) {
-        Ok(_) => {
-            let lines: Vec<&str> = s.split("\n").collect();
-            lines.iter().for_each(|l| {
-                let _l: Vec<&str> = l.split_whitespace().collect();
-                if _l.len() == 2 {
-                    list_a.push(_l[0].parse().unwrap());
-                    list_b.push(_l[1].parse().unwrap())
-                }
-            })
+    for (i, l) in s.split("\n").enumerate() {
+        match parse_line(i, l, Some(expected)) {
+            Ok(Some(numbers)) => {
+                columns
+                    .iter_mut()
+                    .zip(numbers)
+                    .for_each(|(column, n)| column.push(n));
+            }
+            Ok(None) => {}
+            Err(e) => malformed.push(e),
         }
-        Err(e) => panic!("{:?}", e),
     }
+    (columns, malformed)
+}
+
+/// Numbers on line `i` (0 based), `None` if it is blank
+fn parse_line(i: usize, l: &str, expected: Option<usize>) -> Result<Option<Vec<i32>>, String> {
+    let _l: Vec<&str> = l.split_whitespace().collect();
+    if _l.is_empty() {
+        return Ok(None);
+    }
+    if let Some(expected) = expected.filter(|&n| n != _l.len()) {
+        return Err(format!(
+            "line {}: expected {} numbers, found {}",
+            i + 1,
+            expected,
+            _l.len()
+        ));
+    }
+    _l.iter()
+        .map(|v| {
+            v.parse::<i32>()
+                .map_err(|e| format!("line {}: {:?}: {}", i + 1, v, e))
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+fn distance(list_a: &[i32], list_b: &[i32]) -> i64 {
+    let mut list_a = list_a.to_vec();
+    let mut list_b = list_b.to_vec();
 
     // sort the lists
     list_a.sort();
     list_b.sort();
 
-    // pair and calculate distance
-    let results: Vec<i32> = list_a
+    sorted_distance(&list_a, &list_b)
+}
+
+fn sorted_distance(list_a: &[i32], list_b: &[i32]) -> i64 {
+    // pair, calculate distance and sum
+    list_a
         .iter()
         .zip(list_b.iter())
-        .map(|(a, b)| (a - b).abs())
-        .collect();
+        .map(|(&a, &b)| (a as i64 - b as i64).abs())
+        .sum()
+}
 
-    // sum
-    let sum: i32 = results.iter().sum();
+fn similarity(list_a: &[i32], list_b: &[i32]) -> i64 {
+    // count how many times each element of A appears in B
+    let count_a: Counter<i32> = list_a.iter().copied().collect();
+    let count_b: Counter<i32> = list_b.iter().copied().collect();
 
-    println!("Sum: {:?}", sum);
+    count_a.dot_by(&count_b, |x| *x as i64)
+}
 
-    // count how many times each element of A appears in B
-    let sim: Vec<i32> = list_a
+/// Distance and similarity between every pair of columns
+fn matrices(columns: &[Vec<i32>]) -> (Vec<Vec<i64>>, Vec<Vec<i64>>) {
+    // sort and count each column once, not once per pair
+    let sorted: Vec<Vec<i32>> = columns
         .iter()
-        .map(|x| {
-            let count = list_b.iter().filter(|y| x == *y).count() as i32;
-            x * count
+        .map(|c| {
+            let mut c = c.clone();
+            c.sort();
+            c
         })
         .collect();
+    let counts: Vec<Counter<i32>> = columns
+        .iter()
+        .map(|c| c.iter().copied().collect())
+        .collect();
+
+    let distances = sorted
+        .iter()
+        .map(|a| sorted.iter().map(|b| sorted_distance(a, b)).collect())
+        .collect();
+    let similarities = counts
+        .iter()
+        .map(|a| counts.iter().map(|b| a.dot_by(b, |x| *x as i64)).collect())
+        .collect();
+    (distances, similarities)
+}
+
+/// Distance and similarity of a two column input, `malformed` gets a
+/// message for every line that is skipped
+///
+/// Only `run_len` values per column and one line are held in memory at once.
+fn stream(
+    mut input: impl BufRead,
+    run_len: usize,
+    mut malformed: impl FnMut(String),
+) -> io::Result<(i64, i64)> {
+    let mut sorters = [Sorter::new(run_len), Sorter::new(run_len)];
+    let mut line = vec![];
+    for i in 0.. {
+        line.clear();
+        if (&mut input)
+            .take(MAX_LINE as u64 + 1)
+            .read_until(b'\n', &mut line)?
+            == 0
+        {
+            break;
+        }
+        if line.len() > MAX_LINE && line.last() != Some(&b'\n') {
+            malformed(format!("line {}: longer than {} bytes", i + 1, MAX_LINE));
+            skip_line(&mut input)?;
+            continue;
+        }
+        let Ok(l) = std::str::from_utf8(&line) else {
+            malformed(format!("line {}: not UTF-8", i + 1));
+            continue;
+        };
+        match parse_line(i, l, Some(2)) {
+            Ok(Some(numbers)) => {
+                for (sorter, n) in sorters.iter_mut().zip(numbers) {
+                    sorter.push(n)?;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => malformed(e),
+        }
+    }
+    let [a, b] = sorters;
+    let (a, b) = (a.finish()?, b.finish()?);
+
+    let mut distance = 0;
+    let mut similarity = 0;
+    // How many values of each column are <= the last value
+    let mut seen = [0i64; 2];
+    // How many times the last value appears in each column
+    let mut group = [0i64; 2];
+    let mut last: Option<i32> = None;
+    for item in extsort::merge(&[&a, &b])? {
+        let (value, column) = item?;
+        if let Some(prev) = last.filter(|&prev| prev != value) {
+            // Between prev and value, |seen_a - seen_b| pairs straddle the gap
+            distance += (value as i64 - prev as i64) * (seen[0] - seen[1]).abs();
+            similarity += prev as i64 * group[0] * group[1];
+            group = [0, 0];
+        }
+        seen[column] += 1;
+        group[column] += 1;
+        last = Some(value);
+    }
+    if let Some(prev) = last {
+        similarity += prev as i64 * group[0] * group[1];
+    }
+    Ok((distance, similarity))
+}
 
-    let sim_sum: i32 = sim.iter().sum();
+/// Drop the rest of the current line, without buffering it
+fn skip_line(input: &mut impl BufRead) -> io::Result<()> {
+    loop {
+        let buf = input.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(());
+        }
+        match buf.iter().position(|&b| b == b'\n') {
+            Some(i) => {
+                input.consume(i + 1);
+                return Ok(());
+            }
+            None => {
+                let n = buf.len();
+                input.consume(n);
+            }
+        }
+    }
+}
+
+fn format_matrix(title: &str, matrix: &[Vec<i64>]) -> String {
+    let width = matrix
+        .iter()
+        .flatten()
+        .map(|v| v.to_string().len())
+        .max()
+        .unwrap_or(1)
+        .max(3);
 
-    println!("Sim sum: {:?}", sim_sum)
+    let mut out = format!("{}\n{:>4}", title, "");
+    for j in 0..matrix.len() {
+        out += &format!(" {:>width$}", format!("c{}", j + 1));
+    }
+    out.push('\n');
+    for (i, row) in matrix.iter().enumerate() {
+        out += &format!("{:>4}", format!("c{}", i + 1));
+        for v in row {
+            out += &format!(" {:>width$}", v);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn main() {
+    let input_f = Path::new("inputs/input01.txt");
+    if flag("--external") {
+        let run_len =
+            option("--run-size").map_or(1 << 20, |n| n.parse().expect("invalid --run-size"));
+        let file = match File::open(input_f) {
+            Ok(file) => file,
+            Err(e) => panic!("could not open file {}: {}!", input_f.display(), e),
+        };
+        let (sum, sim_sum) = stream(BufReader::new(file), run_len, |m| {
+            eprintln!("skipping {}", m)
+        })
+        .unwrap_or_else(|e| panic!("{:?}", e));
+        println!("Sum: {:?}", sum);
+        println!("Sim sum: {:?}", sim_sum);
+        return;
+    }
+
+    // load
+    let mut file = match File::open(input_f) {
+        Ok(file) => file,
+        Err(e) => panic!("could not open file {}: {}!", input_f.display(), e),
+    };
+
+    let mut s = String::new();
+    let (columns, malformed) = match file.read_to_string(&mut s) {
+        Ok(_) => parse(&s),
+        Err(e) => panic!("{:?}", e),
+    };
+    for m in &malformed {
+        eprintln!("skipping {}", m);
+    }
+
+    if let [list_a, list_b] = &columns[..] {
+        let sum = distance(list_a, list_b);
+        println!("Sum: {:?}", sum);
+
+        let sim_sum = similarity(list_a, list_b);
+        println!("Sim sum: {:?}", sim_sum)
+    } else {
+        let (distances, similarities) = matrices(&columns);
+        print!("{}", format_matrix("Sum:", &distances));
+        print!("{}", format_matrix("Sim sum:", &similarities));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_example_data() {
+        let (columns, malformed) = parse(include_str!("../../inputs/example_input01.txt"));
+        assert!(malformed.is_empty());
+
+        assert_eq!(distance(&columns[0], &columns[1]), 11);
+        assert_eq!(similarity(&columns[0], &columns[1]), 31);
+    }
+
+    #[test]
+    fn test_stream() {
+        let example = include_str!("../../inputs/example_input01.txt");
+        for run_len in [1, 2, 4, 100] {
+            let mut malformed = vec![];
+            let scores = stream(example.as_bytes(), run_len, |m| malformed.push(m)).unwrap();
+            assert_eq!(scores, (11, 31));
+            assert!(malformed.is_empty());
+        }
+
+        let input = "-5 7\n1 2 3\n7 7\n\n0 -5\n";
+        let (columns, _) = parse(input);
+        let mut malformed = vec![];
+        let (sum, sim_sum) = stream(input.as_bytes(), 2, |m| malformed.push(m)).unwrap();
+        assert_eq!(sum, distance(&columns[0], &columns[1]));
+        assert_eq!(sim_sum, similarity(&columns[0], &columns[1]));
+        assert_eq!(malformed, ["line 2: expected 2 numbers, found 3"]);
+
+        // Long lines are skipped whole, without ending up in one buffer
+        let mut input = b"1 2\n".to_vec();
+        input.extend(std::iter::repeat_n(b'9', 3 * MAX_LINE));
+        input.extend(b"\n\xff 1\n3 4\n");
+        let mut malformed = vec![];
+        let scores = stream(&input[..], 2, |m| malformed.push(m)).unwrap();
+        assert_eq!(scores, (distance(&[1, 3], &[2, 4]), 0));
+        assert_eq!(
+            malformed,
+            [
+                format!("line 2: longer than {} bytes", MAX_LINE),
+                "line 3: not UTF-8".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_most_common_column_count() {
+        let (columns, malformed) = parse("42\n3 4\n4 3 9\n2 5\n");
+        assert_eq!(columns, [vec![3, 2], vec![4, 5]]);
+        assert_eq!(
+            malformed,
+            [
+                "line 1: expected 2 numbers, found 1",
+                "line 3: expected 2 numbers, found 3"
+            ]
+        );
+        assert_eq!(parse("\n\n"), (vec![], vec![]));
+    }
+
+    #[test]
+    fn test_matrices() {
+        let (columns, malformed) = parse("3 4 1\n4 3 1\n2 5\n1 3 x\n\n3 9 2\n");
+        assert_eq!(
+            malformed,
+            [
+                "line 3: expected 3 numbers, found 2",
+                "line 4: \"x\": invalid digit found in string"
+            ]
+        );
+        assert_eq!(columns, [vec![3, 4, 3], vec![4, 3, 9], vec![1, 1, 2]]);
+
+        let (distances, similarities) = matrices(&columns);
+        assert_eq!(distances, [[0, 6, 6], [6, 0, 12], [6, 12, 0]]);
+        assert_eq!(distances[0][1], distance(&columns[0], &columns[1]));
+        assert_eq!(similarities[0][1], similarity(&columns[0], &columns[1]));
+        assert_eq!(similarities[1][1], 4 + 3 + 9);
+        assert_eq!(
+            format_matrix("Sum:", &distances),
+            "Sum:\n      c1  c2  c3\n  c1   0   6   6\n  c2   6   0  12\n  c3   6  12   0\n"
+        );
+    }
 }
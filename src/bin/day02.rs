@@ -6,88 +6,232 @@
 // - check by how much they are increasing
 // - judge if they are safe
 // Part 2
-// - apply problem dampener; do this by generating all possible levels
-// - check if any of the possible levels are safe
+// - apply problem dampener: remove up to k levels so that the rest is safe
+// - for each direction, find the longest run of levels that can be kept,
+//   each kept level coming from one of the k + 1 levels before it
+// - that is O(n * k) per report, and tells which levels were removed
+// - `--tolerance K` sets how many levels the dampener may remove, 1 by default
+// Rules
+// - step sizes, direction and flat steps can be changed from the command
+//   line, see `SafetyRules::from_args`
+// Explain
+// - `--explain` lists every unsafe report with what is wrong, and where
+// - `--explain-csv PATH` writes the same list as CSV
 
+use aoc2024::{flag, option, parallel};
 use core::panic;
-use std::{cmp::Ordering, collections::HashSet, fs::File, io::Read, path::Path};
+use std::{fs::File, io::Read, path::Path};
 
 #[derive(Debug)]
 struct Report {
     levels: Vec<i32>,
 }
 
-#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
 enum Direction {
     Increasing,
     Decreasing,
-    Invalid,
 }
 
-impl Report {
-    fn new(levels: Vec<i32>) -> Report {
-        Report { levels }
-    }
+/// What to do with two equal levels in a row
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum Plateau {
+    Reject,
+    Allow,
+}
 
-    fn has_valid_direction(&self) -> bool {
-        let mut r: HashSet<Direction> = HashSet::new();
+#[derive(Debug, Clone, Copy)]
+struct SafetyRules {
+    /// Smallest change between two levels, flat steps aside
+    min_step: u32,
+    /// Largest change between two levels
+    max_step: u32,
+    /// Must all the changes go the same way?
+    monotonic: bool,
+    plateaus: Plateau,
+}
 
-        self.levels.windows(2).for_each(|w| {
-            r.insert(match w[0].cmp(&w[1]) {
-                Ordering::Greater => Direction::Increasing,
-                Ordering::Less => Direction::Decreasing,
-                Ordering::Equal => Direction::Invalid,
-            });
-        });
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum ViolationKind {
+    /// The report changed direction
+    DirectionFlip,
+    /// Two equal levels in a row
+    Plateau,
+    StepTooBig,
+    StepTooSmall,
+}
 
-        r.len() == 1
+impl ViolationKind {
+    fn name(&self) -> &'static str {
+        match self {
+            ViolationKind::DirectionFlip => "direction flip",
+            ViolationKind::Plateau => "plateau",
+            ViolationKind::StepTooBig => "step too big",
+            ViolationKind::StepTooSmall => "step too small",
+        }
     }
+}
 
-    fn change_range(&self) -> i32 {
-        let change: Vec<i32> = self
-            .levels
-            .windows(2)
-            .map(|w| (w[0] - w[1]).abs())
-            .collect();
+/// A rule broken by the step from level `level - 1` to `level`
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+struct Violation {
+    kind: ViolationKind,
+    level: usize,
+}
 
-        match change.iter().max() {
-            Some(v) => *v,
-            None => 0,
+impl Default for SafetyRules {
+    /// The rules from the puzzle
+    fn default() -> Self {
+        SafetyRules {
+            min_step: 1,
+            max_step: 3,
+            monotonic: true,
+            plateaus: Plateau::Reject,
         }
     }
+}
 
-    fn is_safe(&self) -> bool {
-        // apply problem dampener!
-        //  -- generate all possible levels and check if any of them are safe
-        let pos_reports = &self.generate_level_pos();
+impl SafetyRules {
+    /// Puzzle rules changed by `--min-step N`, `--max-step N`,
+    /// `--any-direction` and `--allow-plateaus`
+    fn from_args() -> Self {
+        let mut rules = SafetyRules::default();
+        if let Some(n) = option("--min-step") {
+            rules.min_step = n.parse().expect("invalid --min-step");
+        }
+        if let Some(n) = option("--max-step") {
+            rules.max_step = n.parse().expect("invalid --max-step");
+        }
+        if flag("--any-direction") {
+            rules.monotonic = false;
+        }
+        if flag("--allow-plateaus") {
+            rules.plateaus = Plateau::Allow;
+        }
+        rules
+    }
+
+    /// Can level `b` come right after level `a`, in a report going `direction`?
+    fn allows(&self, direction: Direction, a: i32, b: i32) -> bool {
+        if a == b {
+            return self.plateaus == Plateau::Allow;
+        }
+        let wrong_way = match direction {
+            Direction::Increasing => b < a,
+            Direction::Decreasing => b > a,
+        };
+        (!self.monotonic || !wrong_way) && (self.min_step..=self.max_step).contains(&a.abs_diff(b))
+    }
+}
 
-        let mut safe_possibilities: Vec<bool> = vec![];
-        pos_reports.iter().for_each(|r| {
-            if r.change_range() <= 3 && r.has_valid_direction() {
-                safe_possibilities.push(true);
+impl Report {
+    fn new(levels: Vec<i32>) -> Report {
+        Report { levels }
+    }
+
+    fn is_safe(&self, rules: &SafetyRules) -> bool {
+        self.violations(rules).is_empty()
+    }
+
+    /// Every broken rule, the direction is set by the first step that is not flat
+    fn violations(&self, rules: &SafetyRules) -> Vec<Violation> {
+        let mut direction = None;
+        let mut violations = vec![];
+        for (i, w) in self.levels.windows(2).enumerate() {
+            let (a, b) = (w[0], w[1]);
+            let step = a.abs_diff(b);
+            let kind = if a == b {
+                (rules.plateaus == Plateau::Reject).then_some(ViolationKind::Plateau)
+            } else if rules.monotonic && *direction.get_or_insert(b > a) != (b > a) {
+                Some(ViolationKind::DirectionFlip)
+            } else if step > rules.max_step {
+                Some(ViolationKind::StepTooBig)
+            } else if step < rules.min_step {
+                Some(ViolationKind::StepTooSmall)
             } else {
-                safe_possibilities.push(false);
+                None
+            };
+            if let Some(kind) = kind {
+                violations.push(Violation { kind, level: i + 1 });
             }
-        });
+        }
+        violations
+    }
 
-        safe_possibilities.iter().any(|&x| x)
+    /// Indices of the levels to remove to make the report safe, `None` if
+    /// more than `tolerance` would have to go
+    fn dampen(&self, rules: &SafetyRules, tolerance: usize) -> Option<Vec<usize>> {
+        [Direction::Increasing, Direction::Decreasing]
+            .into_iter()
+            .filter_map(|direction| self.dampen_towards(rules, direction, tolerance))
+            .min_by_key(|removed| removed.len())
     }
 
-    fn generate_level_pos(&self) -> Vec<Report> {
-        let mut c = 0;
-        let r = self
-            .levels
-            .iter()
-            .map(|_| {
-                let mut new_levels = self.levels.clone();
-                new_levels.remove(c);
-                c += 1;
-                Report::new(new_levels)
-            })
-            .collect();
+    fn dampen_towards(
+        &self,
+        rules: &SafetyRules,
+        direction: Direction,
+        tolerance: usize,
+    ) -> Option<Vec<usize>> {
+        let n = self.levels.len();
+        // removed[j]: fewest levels removed before j, when level j is kept
+        // from[j]: the level kept right before j
+        let mut removed: Vec<Option<usize>> = vec![None; n];
+        let mut from: Vec<Option<usize>> = vec![None; n];
+        for j in 0..n {
+            // Start the report here, removing everything before
+            if j <= tolerance {
+                removed[j] = Some(j);
+            }
+            // Or carry on from a level kept at most `tolerance` levels back
+            for p in j.saturating_sub(tolerance + 1)..j {
+                let Some(before) = removed[p] else {
+                    continue;
+                };
+                let total = before + (j - p - 1);
+                if total <= tolerance
+                    && rules.allows(direction, self.levels[p], self.levels[j])
+                    && removed[j].is_none_or(|r| total < r)
+                {
+                    removed[j] = Some(total);
+                    from[j] = Some(p);
+                }
+            }
+        }
+
+        // End the report at the best level, removing everything after
+        let (last, total) = (0..n)
+            .filter_map(|j| Some((j, removed[j]? + (n - 1 - j))))
+            .min_by_key(|&(_, total)| total)
+            .unwrap_or((0, 0));
+        if total > tolerance {
+            return None;
+        }
+
+        // Walk back through the kept levels, the others were removed
+        let mut kept = vec![false; n];
+        let mut level = (n > 0).then_some(last);
+        while let Some(j) = level {
+            kept[j] = true;
+            level = from[j];
+        }
+        Some((0..n).filter(|&j| !kept[j]).collect())
+    }
+}
 
-        r
+fn parse_reports(s: &str) -> Result<Vec<Report>, String> {
+    let mut reports: Vec<Report> = vec![];
+    for (i, l) in s.split("\n").enumerate() {
+        let levels: Vec<i32> = l
+            .split_whitespace()
+            .map(|v| v.parse())
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("line {}: {}", i + 1, e))?;
+        if !levels.is_empty() {
+            reports.push(Report::new(levels))
+        }
     }
+    Ok(reports)
 }
 
 fn load_reports(input_f: &str) -> Vec<Report> {
@@ -98,28 +242,265 @@ fn load_reports(input_f: &str) -> Vec<Report> {
         Err(e) => panic!("could not open file {}: {}!", input_f.display(), e),
     };
 
-    let mut reports: Vec<Report> = vec![];
-
     let mut s = String::new();
     match file.read_to_string(&mut s) {
-        Ok(_) => {
-            let lines: Vec<&str> = s.split("\n").collect();
-            lines.iter().for_each(|l| {
-                let levels: Vec<i32> = l.split_whitespace().map(|v| v.parse().unwrap()).collect();
-                if !levels.is_empty() {
-                    reports.push(Report::new(levels))
-                }
-            })
-        }
+        Ok(_) => parse_reports(&s).unwrap_or_else(|e| panic!("{}", e)),
         Err(e) => panic!("{:?}", e),
     }
-    reports
+}
+
+/// One line per violation of every unsafe report, with the levels the
+/// dampener would remove
+fn explain(reports: &[Report], rules: &SafetyRules, tolerance: usize) -> String {
+    let mut out = String::new();
+    for (i, report) in reports.iter().enumerate() {
+        let violations = report.violations(rules);
+        if violations.is_empty() {
+            continue;
+        }
+        out += &format!("report {}: {:?}\n", i + 1, report.levels);
+        for v in &violations {
+            out += &format!("  level {}: {}\n", v.level, v.kind.name());
+        }
+        match report.dampen(rules, tolerance) {
+            Some(removed) => out += &format!("  safe without levels {:?}\n", removed),
+            None => out += "  unsafe even with the dampener\n",
+        }
+    }
+    out
+}
+
+/// Same as `explain`, as CSV with one row per violation
+fn explain_csv(reports: &[Report], rules: &SafetyRules, tolerance: usize) -> String {
+    let join = |values: &[usize]| {
+        values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let mut out = String::from("report,levels,level,violation,removed\n");
+    for (i, report) in reports.iter().enumerate() {
+        let levels: Vec<String> = report.levels.iter().map(|l| l.to_string()).collect();
+        // Empty when the dampener cannot save the report
+        let removed = report
+            .dampen(rules, tolerance)
+            .map_or("".to_string(), |removed| join(&removed));
+        for v in report.violations(rules) {
+            out += &format!(
+                "{},{},{},{},{}\n",
+                i + 1,
+                levels.join(" "),
+                v.level,
+                v.kind.name(),
+                removed
+            );
+        }
+    }
+    out
 }
 
 fn main() {
+    parallel::configure();
+    let rules = SafetyRules::from_args();
     let reports = load_reports("inputs/day02.txt");
 
-    let safe_reports = reports.iter().filter(|r| r.is_safe()).count();
+    let tolerance = option("--tolerance").map_or(1, |k| k.parse().expect("invalid --tolerance"));
+
+    let safe_reports = parallel::filter(&reports, |r| r.is_safe(&rules)).len();
+    println!("{:?} reports are safe", safe_reports);
 
-    println!("{:?} reports are safe", safe_reports)
+    let dampened_reports =
+        parallel::filter(&reports, |r| r.dampen(&rules, tolerance).is_some()).len();
+    println!("{:?} reports are safe with the dampener", dampened_reports);
+
+    if flag("--explain") {
+        print!("{}", explain(&reports, &rules, tolerance));
+    }
+    if let Some(path) = option("--explain-csv") {
+        std::fs::write(&path, explain_csv(&reports, &rules, tolerance))
+            .unwrap_or_else(|e| panic!("could not write {}: {}", path, e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use itertools::Itertools;
+    use proptest::prelude::*;
+
+    // Straight from the puzzle text, no cleverness
+    fn naive_is_safe(levels: &[i32]) -> bool {
+        let increasing = levels.windows(2).all(|w| w[1] > w[0] && w[1] - w[0] <= 3);
+        let decreasing = levels.windows(2).all(|w| w[0] > w[1] && w[0] - w[1] <= 3);
+        increasing || decreasing
+    }
+
+    fn naive_follows(rules: &SafetyRules, levels: &[i32]) -> bool {
+        let step_ok = |a: i32, b: i32| {
+            if a == b {
+                rules.plateaus == Plateau::Allow
+            } else {
+                (rules.min_step..=rules.max_step).contains(&a.abs_diff(b))
+            }
+        };
+        let steps_ok = levels.windows(2).all(|w| step_ok(w[0], w[1]));
+        let increasing = levels.windows(2).all(|w| w[1] >= w[0]);
+        let decreasing = levels.windows(2).all(|w| w[1] <= w[0]);
+        steps_ok && (!rules.monotonic || increasing || decreasing)
+    }
+
+    fn naive_is_safe_dampened(levels: &[i32]) -> bool {
+        naive_is_safe(levels)
+            || (0..levels.len()).any(|i| {
+                let mut l = levels.to_vec();
+                l.remove(i);
+                naive_is_safe(&l)
+            })
+    }
+
+    #[test]
+    fn test_example_data() {
+        let reports = load_reports("inputs/example_day02.txt");
+
+        let rules = SafetyRules::default();
+        assert_eq!(reports.iter().filter(|r| r.is_safe(&rules)).count(), 2);
+        assert_eq!(
+            reports
+                .iter()
+                .filter(|r| r.dampen(&rules, 1).is_some())
+                .count(),
+            4
+        );
+        assert!(parse_reports("1 2 x").is_err());
+    }
+
+    #[test]
+    fn test_dampen_indices() {
+        let rules = SafetyRules::default();
+        let report = Report::new(vec![1, 3, 2, 4, 5]);
+        assert_eq!(report.dampen(&rules, 0), None);
+        assert_eq!(report.dampen(&rules, 1), Some(vec![2]));
+
+        let report = Report::new(vec![9, 1, 2, 9, 3, 4, 0]);
+        assert_eq!(report.dampen(&rules, 2), None);
+        assert_eq!(report.dampen(&rules, 3), Some(vec![0, 3, 6]));
+        assert_eq!(
+            Report::new(vec![5, 5, 5]).dampen(&rules, 2),
+            Some(vec![1, 2])
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        let reports = load_reports("inputs/example_day02.txt");
+        let rules = SafetyRules::default();
+        let violations: Vec<Vec<Violation>> =
+            reports.iter().map(|r| r.violations(&rules)).collect();
+        let v = |kind, level| Violation { kind, level };
+        assert_eq!(
+            violations,
+            [
+                vec![],
+                vec![v(ViolationKind::StepTooBig, 2)],
+                vec![v(ViolationKind::StepTooBig, 3)],
+                vec![v(ViolationKind::DirectionFlip, 2)],
+                vec![v(ViolationKind::Plateau, 3)],
+                vec![],
+            ]
+        );
+
+        let text = explain(&reports, &rules, 1);
+        assert!(text.starts_with(
+            "report 2: [1, 2, 7, 8, 9]\n  level 2: step too big\n  unsafe even with the dampener\n"
+        ));
+        let csv = explain_csv(&reports, &rules, 1);
+        assert_eq!(
+            csv,
+            "report,levels,level,violation,removed\n\
+             2,1 2 7 8 9,2,step too big,\n\
+             3,9 7 6 2 1,3,step too big,\n\
+             4,1 3 2 4 5,2,direction flip,2\n\
+             5,8 6 4 4 1,3,plateau,3\n"
+        );
+    }
+
+    #[test]
+    fn test_flat_and_single_level_reports() {
+        let rules = SafetyRules::default();
+        assert_eq!(Report::new(vec![4, 4, 4]).dampen(&rules, 0), None);
+        assert_eq!(Report::new(vec![4, 4]).dampen(&rules, 0), None);
+        assert_eq!(Report::new(vec![4]).dampen(&rules, 0), Some(vec![]));
+        assert!(Report::new(vec![1, 1, 5]).dampen(&rules, 1).is_none());
+
+        let plateaus = SafetyRules {
+            plateaus: Plateau::Allow,
+            ..rules
+        };
+        assert_eq!(
+            Report::new(vec![4, 4, 5, 5]).dampen(&plateaus, 0),
+            Some(vec![])
+        );
+        assert_eq!(Report::new(vec![4, 4, 3, 5]).dampen(&plateaus, 0), None);
+
+        let zigzag = Report::new(vec![1, 4, 2, 5, 3]);
+        assert_eq!(zigzag.dampen(&rules, 1), None);
+        assert_eq!(zigzag.dampen(&rules, 2), Some(vec![2, 4]));
+        let any_direction = SafetyRules {
+            monotonic: false,
+            min_step: 2,
+            ..rules
+        };
+        assert_eq!(zigzag.dampen(&any_direction, 0), Some(vec![]));
+    }
+
+    proptest! {
+        #[test]
+        fn test_is_safe_matches_naive(levels in prop::collection::vec(0..12i32, 3..8)) {
+            let report = Report::new(levels.clone());
+            let rules = SafetyRules::default();
+            prop_assert_eq!(report.is_safe(&rules), naive_is_safe(&levels));
+            prop_assert_eq!(report.dampen(&rules, 1).is_some(), naive_is_safe_dampened(&levels));
+        }
+
+        #[test]
+        fn test_dampen_matches_naive(
+            levels in prop::collection::vec(0..12i32, 0..9),
+            tolerance in 0..4usize,
+            (min_step, max_step) in (0..3u32, 0..5u32),
+            monotonic: bool,
+            plateaus: bool,
+        ) {
+            let rules = SafetyRules {
+                min_step,
+                max_step,
+                monotonic,
+                plateaus: if plateaus { Plateau::Allow } else { Plateau::Reject },
+            };
+            // Fewest removals that leave a safe report, trying every subset
+            let fewest = (0..=levels.len()).find(|&r| {
+                (0..levels.len()).combinations(r).any(|removed| {
+                    let kept: Vec<i32> = (0..levels.len())
+                        .filter(|i| !removed.contains(i))
+                        .map(|i| levels[i])
+                        .collect();
+                    naive_follows(&rules, &kept)
+                })
+            });
+
+            let report = Report::new(levels.clone());
+            prop_assert_eq!(report.is_safe(&rules), fewest == Some(0));
+            let removed = report.dampen(&rules, tolerance);
+            prop_assert_eq!(removed.is_some(), fewest.is_some_and(|r| r <= tolerance));
+            if let Some(removed) = removed {
+                prop_assert_eq!(Some(removed.len()), fewest);
+                let kept: Vec<i32> = (0..levels.len())
+                    .filter(|i| !removed.contains(i))
+                    .map(|i| levels[i])
+                    .collect();
+                prop_assert!(naive_follows(&rules, &kept));
+            }
+        }
+    }
 }
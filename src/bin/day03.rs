@@ -1,87 +1,739 @@
 // https://adventofcode.com/2024/day/3
 //
 // Part 1
-// - compile regex
-// - capture the matches in the input
+// - scan the memory once, left to right, for `mul(a,b)`, `do()` and `don't()`
+// - every token keeps its byte offset in the memory
 // - do the multiplication operation
 // - sum the results
 // Part 2
-// - compile new regex to identify the blocks
-// - re-apply regex from part1
-// - sum the results
-
-use regex::Regex;
+// - run the same tokens in order, `do()` and `don't()` switch `mul` on and off
+// - sum the results done while enabled
+// Instruction set
+// - `mul`, `do` and `don't` are entries in a `Registry`, other instructions
+//   can be added by implementing `Instruction`
+// - the scanner looks for every registered name, longest first, followed by
+//   `(`, the arguments separated by `,` and `)`
+// - a name and `(` that do not go on into a valid instruction are a near
+//   miss, they are listed with `--near-misses`; a name alone is too common
+//   in memory dumps (`undo`, `done`) to report
+// - `mul` only takes 1 to 3 digit numbers, totals are checked for overflow
+// Streaming
+// - memory is read through a fixed-size buffer, instructions cut by the end
+//   of a read wait in the buffer until the next one completes them
+// Annotate
+// - `--annotate` prints the memory with disabled regions dimmed, each `mul`
+//   followed by its product and each region by its subtotal
+// - it reads the whole memory into RAM, unlike the sums
+// Memory is bytes, not text: corrupted dumps can hold anything, only the
+// instructions have to be ASCII
 
+use aoc2024::flag;
+use aoc2024::render::{stdout_is_tty, Color};
 use core::panic;
-use std::{fs::File, io::Read, path::Path};
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
 
-fn read_file(input_f: &str) -> String {
-    let input_f = Path::new(input_f);
-    let mut file = match File::open(input_f) {
-        Ok(file) => file,
-        Err(e) => panic!("could not open file {}: {}!", input_f.display(), e),
+/// Totals and flags the instructions work on
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct State {
+    enabled: bool,
+    /// Every product, enabled or not
+    part1: i64,
+    /// Products done while enabled
+    part2: i64,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            enabled: true,
+            part1: 0,
+            part2: 0,
+        }
+    }
+}
+
+trait Instruction {
+    fn name(&self) -> &str;
+
+    fn arity(&self) -> usize;
+
+    /// Can `b` be part of an argument?
+    fn arg_byte(&self, b: u8) -> bool {
+        b.is_ascii_digit()
+    }
+
+    /// Is `arg`, made of `arg_byte`s, a valid argument?
+    fn accepts(&self, arg: &[u8]) -> bool {
+        !arg.is_empty()
+    }
+
+    /// Err if the state can't hold the result
+    fn apply(&self, args: &[i64], state: &mut State) -> Result<(), String>;
+}
+
+struct Mul;
+
+impl Instruction for Mul {
+    fn name(&self) -> &str {
+        "mul"
+    }
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    /// 1 to 3 digits
+    fn accepts(&self, arg: &[u8]) -> bool {
+        (1..=3).contains(&arg.len())
+    }
+
+    fn apply(&self, args: &[i64], state: &mut State) -> Result<(), String> {
+        let overflow = || "sum overflows i64".to_string();
+        let product = args[0].checked_mul(args[1]).ok_or_else(overflow)?;
+        state.part1 = state.part1.checked_add(product).ok_or_else(overflow)?;
+        if state.enabled {
+            state.part2 = state.part2.checked_add(product).ok_or_else(overflow)?;
+        }
+        Ok(())
+    }
+}
+
+struct Do;
+
+impl Instruction for Do {
+    fn name(&self) -> &str {
+        "do"
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn apply(&self, _: &[i64], state: &mut State) -> Result<(), String> {
+        state.enabled = true;
+        Ok(())
+    }
+}
+
+struct Dont;
+
+impl Instruction for Dont {
+    fn name(&self) -> &str {
+        "don't"
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn apply(&self, _: &[i64], state: &mut State) -> Result<(), String> {
+        state.enabled = false;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    /// Byte offset of the first character
+    offset: usize,
+    /// Length in bytes
+    len: usize,
+    /// Index of the instruction in the registry
+    instruction: usize,
+    args: Vec<i64>,
+}
+
+/// A registered name and `(` that did not go on into a valid instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NearMiss {
+    offset: usize,
+    /// The memory up to and including the byte where it went wrong
+    text: String,
+    reason: String,
+}
+
+/// What the scanner found at an offset
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Scanned {
+    Token(Token),
+    NearMiss(NearMiss),
+}
+
+/// Why a cursor stopped
+enum Stop {
+    /// The bytes ran out before it could tell
+    Incomplete,
+    /// Not the expected instruction, and why
+    Invalid(String),
+}
+
+/// Reads an instruction byte by byte, giving up at the first unexpected one
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Are there no more bytes after `bytes`?
+    eof: bool,
+}
+
+impl Cursor<'_> {
+    fn eat(&mut self, literal: &[u8]) -> Result<(), Stop> {
+        let rest = &self.bytes[self.pos..];
+        if rest.starts_with(literal) {
+            self.pos += literal.len();
+            Ok(())
+        } else if !self.eof && literal.starts_with(rest) {
+            Err(Stop::Incomplete)
+        } else {
+            Err(Stop::Invalid(format!(
+                "expected {:?}",
+                String::from_utf8_lossy(literal)
+            )))
+        }
+    }
+
+    fn arg(&mut self, instruction: &dyn Instruction) -> Result<i64, Stop> {
+        let len = self.bytes[self.pos..]
+            .iter()
+            .take_while(|&&b| instruction.arg_byte(b))
+            .count();
+        // The argument may go on in the next bytes
+        if !self.eof && self.pos + len == self.bytes.len() {
+            return Err(Stop::Incomplete);
+        }
+        let arg = &self.bytes[self.pos..self.pos + len];
+        if len == 0 {
+            return Err(Stop::Invalid("expected an argument".to_string()));
+        }
+        let n = std::str::from_utf8(arg)
+            .ok()
+            .filter(|_| instruction.accepts(arg))
+            .and_then(|a| a.parse().ok());
+        match n {
+            Some(n) => {
+                self.pos += len;
+                Ok(n)
+            }
+            None => {
+                // Point at the last byte of the argument, it is all wrong
+                self.pos += len - 1;
+                Err(Stop::Invalid(format!(
+                    "argument {:?} rejected",
+                    String::from_utf8_lossy(arg)
+                )))
+            }
+        }
+    }
+}
+
+/// Smallest buffer for `Registry::scan`, enough for any puzzle instruction
+const MIN_BUFFER: usize = 16;
+
+#[derive(Default)]
+struct Registry {
+    instructions: Vec<Box<dyn Instruction>>,
+    /// Indices into `instructions`, longest name first
+    by_length: Vec<usize>,
+}
+
+impl Registry {
+    fn new() -> Self {
+        Registry::default()
+    }
+
+    /// `mul`, `do` and `don't`, as in the puzzle
+    fn puzzle() -> Self {
+        Registry::new().register(Mul).register(Do).register(Dont)
+    }
+
+    fn register(mut self, instruction: impl Instruction + 'static) -> Self {
+        self.instructions.push(Box::new(instruction));
+        self.by_length = (0..self.instructions.len()).collect();
+        self.by_length
+            .sort_by_key(|&i| std::cmp::Reverse(self.instructions[i].name().len()));
+        self
+    }
+
+    /// What starts at `bytes[offset]`, and where to scan next
+    ///
+    /// `None` if `bytes` ends before it is known, and more may follow.
+    /// `base` is the offset of `bytes` in the whole memory.
+    fn scan_at(
+        &self,
+        bytes: &[u8],
+        offset: usize,
+        base: usize,
+        eof: bool,
+    ) -> Option<(Option<Scanned>, usize)> {
+        let mut near_miss = None;
+        for &i in &self.by_length {
+            let instruction = self.instructions[i].as_ref();
+            let mut cursor = Cursor {
+                bytes: &bytes[offset..],
+                pos: 0,
+                eof,
+            };
+            let result = cursor
+                .eat(instruction.name().as_bytes())
+                .and_then(|_| Self::read_args(instruction, &mut cursor));
+            match result {
+                Ok(args) => {
+                    let token = Token {
+                        offset: base + offset,
+                        len: cursor.pos,
+                        instruction: i,
+                        args,
+                    };
+                    return Some((Some(Scanned::Token(token)), offset + cursor.pos));
+                }
+                // A longer name comes first, it must be known before a shorter one
+                Err(Stop::Incomplete) => return None,
+                // Not past the name and `(`
+                Err(Stop::Invalid(_)) if cursor.pos <= instruction.name().len() => {}
+                Err(Stop::Invalid(reason)) => {
+                    near_miss.get_or_insert_with(|| {
+                        let end = (cursor.pos + 1).min(cursor.bytes.len());
+                        Scanned::NearMiss(NearMiss {
+                            offset: base + offset,
+                            text: String::from_utf8_lossy(&cursor.bytes[..end]).into_owned(),
+                            reason,
+                        })
+                    });
+                }
+            }
+        }
+        Some((near_miss, offset + 1))
+    }
+
+    /// `(`, the arguments and `)`
+    fn read_args(instruction: &dyn Instruction, cursor: &mut Cursor) -> Result<Vec<i64>, Stop> {
+        cursor.eat(b"(")?;
+        let mut args = vec![];
+        for k in 0..instruction.arity() {
+            if k > 0 {
+                cursor.eat(b",")?;
+            }
+            args.push(cursor.arg(instruction)?);
+        }
+        cursor.eat(b")")?;
+        Ok(args)
+    }
+
+    /// Scan `bytes` as far as possible, returns how many bytes are done
+    fn scan_chunk(
+        &self,
+        bytes: &[u8],
+        base: usize,
+        eof: bool,
+        visit: &mut impl FnMut(Scanned),
+    ) -> usize {
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let Some((scanned, next)) = self.scan_at(bytes, offset, base, eof) else {
+                break;
+            };
+            scanned.into_iter().for_each(&mut *visit);
+            offset = next;
+        }
+        offset
+    }
+
+    /// Scan everything `reader` gives, with a buffer of `buffer_size` bytes
+    ///
+    /// An instruction cut by the end of the buffer is kept for the next read,
+    /// so memory of any size can be scanned. Only an instruction longer than
+    /// the whole buffer would be missed.
+    fn scan(
+        &self,
+        mut reader: impl Read,
+        buffer_size: usize,
+        mut visit: impl FnMut(Scanned),
+    ) -> io::Result<()> {
+        let mut buffer = vec![0; buffer_size.max(MIN_BUFFER)];
+        // Bytes in the buffer, and offset of the first one in the memory
+        let mut filled = 0;
+        let mut base = 0;
+        loop {
+            let n = match reader.read(&mut buffer[filled..]) {
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            filled += n;
+            let eof = n == 0;
+
+            let mut done = self.scan_chunk(&buffer[..filled], base, eof, &mut visit);
+            if done == 0 && filled == buffer.len() {
+                // Nothing fits, decide with what is there
+                let (scanned, next) = self
+                    .scan_at(&buffer[..filled], 0, base, true)
+                    .expect("scan at the end of memory always decides");
+                scanned.into_iter().for_each(&mut visit);
+                done = next;
+            }
+            buffer.copy_within(done..filled, 0);
+            filled -= done;
+            base += done;
+            if eof {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Every instruction in the memory in order, and the near misses
+    fn tokenize(&self, memory: &[u8]) -> (Vec<Token>, Vec<NearMiss>) {
+        let mut tokens = vec![];
+        let mut near_misses = vec![];
+        self.scan_chunk(memory, 0, true, &mut |scanned| match scanned {
+            Scanned::Token(token) => tokens.push(token),
+            Scanned::NearMiss(near_miss) => near_misses.push(near_miss),
+        });
+        (tokens, near_misses)
+    }
+
+    fn run(&self, token: &Token, state: &mut State) -> Result<(), String> {
+        self.instructions[token.instruction]
+            .apply(&token.args, state)
+            .map_err(|e| format!("offset {}: {}", token.offset, e))
+    }
+}
+
+/// Both parts, reading the memory `buffer_size` bytes at a time
+///
+/// `near_miss` is called for every near miss, as they are found.
+fn solve_stream(
+    reader: impl Read,
+    buffer_size: usize,
+    mut near_miss: impl FnMut(NearMiss),
+) -> Result<(i64, i64), String> {
+    let registry = Registry::puzzle();
+    let mut state = State::default();
+    // The first overflow stops the sums, the scan still has to finish
+    let mut result = Ok(());
+    registry
+        .scan(reader, buffer_size, |scanned| match scanned {
+            Scanned::Token(token) => {
+                if result.is_ok() {
+                    result = registry.run(&token, &mut state);
+                }
+            }
+            Scanned::NearMiss(m) => near_miss(m),
+        })
+        .map_err(|e| format!("{:?}", e))?;
+    result.map(|_| (state.part1, state.part2))
+}
+
+/// The memory with products and region subtotals written in
+///
+/// A region ends at every `do()` and `don't()`. With `color`, disabled
+/// regions are dimmed, counted products are green and skipped ones red.
+/// Products and subtotals that overflow i64 are shown as `overflow`.
+fn annotate(memory: &[u8], color: bool) -> String {
+    let paint = |c: Color, s: &str| if color { c.paint(s) } else { s.to_string() };
+    let registry = Registry::puzzle();
+    let (tokens, _) = registry.tokenize(memory);
+
+    let mut out = String::new();
+    let mut enabled = true;
+    let show = |n: Option<i64>| n.map_or("overflow".to_string(), |n| n.to_string());
+    // Products in the current region, `None` before the first one, and
+    // `Some(None)` once it overflows
+    let mut subtotal: Option<Option<i64>> = None;
+    let end_region = |out: &mut String, enabled: bool, subtotal: &mut Option<Option<i64>>| {
+        if let Some(sum) = subtotal.take() {
+            let state = if enabled { "enabled" } else { "disabled" };
+            *out += &paint(
+                Color::Blue,
+                &format!("{{{} subtotal: {}}}", state, show(sum)),
+            );
+        }
     };
 
-    let mut s = String::new();
-    match file.read_to_string(&mut s) {
-        Ok(_) => {
-            let lines: Vec<&str> = s.split("\n").collect();
-            lines.join("")
+    let mut pos = 0;
+    for token in &tokens {
+        let text = &String::from_utf8_lossy(&memory[pos..token.offset]);
+        out += &if enabled {
+            text.to_string()
+        } else {
+            paint(Color::Dim, text)
+        };
+        let instruction = &String::from_utf8_lossy(&memory[token.offset..token.offset + token.len]);
+        pos = token.offset + token.len;
+
+        match registry.instructions[token.instruction].name() {
+            "mul" => {
+                let product = token.args[0].checked_mul(token.args[1]);
+                let highlight = if enabled { Color::Green } else { Color::Red };
+                out += &paint(highlight, instruction);
+                out += &paint(Color::Yellow, &format!("[={}]", show(product)));
+                let sum = subtotal.get_or_insert(Some(0));
+                *sum = sum.zip(product).and_then(|(s, p)| s.checked_add(p));
+            }
+            name => {
+                end_region(&mut out, enabled, &mut subtotal);
+                out += &paint(Color::Cyan, instruction);
+                enabled = name != "don't";
+            }
         }
-        Err(e) => panic!("{:?}", e),
     }
+    let text = &String::from_utf8_lossy(&memory[pos..]);
+    out += &if enabled {
+        text.to_string()
+    } else {
+        paint(Color::Dim, text)
+    };
+    end_region(&mut out, enabled, &mut subtotal);
+    out
 }
 
-fn do_multiplications(m: &str) -> i32 {
-    let r: Vec<i32> = m.split(',').map(|v| v.parse::<i32>().unwrap()).collect();
-    r[0] * r[1]
+fn open(input_f: &str) -> File {
+    let input_f = Path::new(input_f);
+    match File::open(input_f) {
+        Ok(file) => file,
+        Err(e) => panic!("could not open file {}: {}!", input_f.display(), e),
+    }
 }
 
 fn main() {
-    // Identify mul operations "NN,NN" https://regex101.com/r/YqXLQt/1
-    let mul_re = Regex::new(r"ul\((\d+,\d+)\)").unwrap();
+    // Stream, the memory can be larger than RAM
+    let input = open("inputs/input03.txt");
+
+    let show_near_misses = flag("--near-misses");
+    let (part1, part2) = solve_stream(input, 64 * 1024, |m| {
+        if show_near_misses {
+            println!(
+                "{:>8} {:<16} {}",
+                m.offset,
+                format!("{:?}", m.text),
+                m.reason
+            );
+        }
+    })
+    .unwrap_or_else(|e| panic!("{}", e));
+    println!("mul sum: {:?}", part1);
+    println!("enabled mul sum: {:?}", part2);
+
+    if flag("--annotate") {
+        // The annotated copy is built in memory, so this reads it all at once
+        let mut memory = vec![];
+        open("inputs/input03.txt")
+            .read_to_end(&mut memory)
+            .unwrap_or_else(|e| panic!("{:?}", e));
+        println!("{}", annotate(&memory, stdout_is_tty()));
+    }
+}
 
-    // Identify commands blocks "do()xxxxdon't()" -https://regex101.com/r/x1X7y1/1
-    let valid_block_re = Regex::new(r"(?m)(do\(\).*?(?:mul\(\d+,\d+\)).*?don't\(\))").unwrap();
+#[cfg(test)]
+mod tests {
 
-    // Identify first command block "xxxxxdo()" - https://regex101.com/r/rxchKg/1
-    let first_block_re = Regex::new(r"(?m)(^.*?(?:mul\(\d+,\d+\)).*?do\(\){1})").unwrap();
+    use super::*;
 
-    // Load
-    let input = read_file("inputs/input03.txt");
+    fn solve(memory: &[u8]) -> Result<(i64, i64), String> {
+        solve_stream(memory, MIN_BUFFER, |_| {})
+    }
 
-    // Part 1
-    let mut result: Vec<i32> = vec![];
-    for (_, [n]) in mul_re.captures_iter(&input).map(|c| c.extract()) {
-        let mul = do_multiplications(n);
-        result.push(mul)
+    #[test]
+    fn test_example_data() {
+        let input = open("inputs/example_input03.txt");
+        assert_eq!(solve_stream(input, 4096, |_| {}), Ok((161, 48)));
     }
 
-    let sum: i32 = result.iter().sum();
-    println!("mul sum: {:?}", sum);
+    #[test]
+    fn test_tokenize() {
+        let registry = Registry::puzzle();
+        let (tokens, _) = registry.tokenize(b"mulmul(2,3)don't()_mul(4,5)mul(1,2undo()");
+        let token = |offset, len, instruction, args: &[i64]| Token {
+            offset,
+            len,
+            instruction,
+            args: args.to_vec(),
+        };
+        assert_eq!(
+            tokens,
+            [
+                token(3, 8, 0, &[2, 3]),
+                token(11, 7, 2, &[]),
+                token(19, 8, 0, &[4, 5]),
+                token(36, 4, 1, &[]),
+            ]
+        );
+        // No `mul` needed before the first `do()` or between toggles
+        assert_eq!(solve(b"do()don't()mul(2,2)"), Ok((4, 0)));
+        assert_eq!(solve(b"mul(2,2)\ndo()mul(3,3)"), Ok((13, 13)));
+    }
 
-    // Part 2
-    let mut result: Vec<i32> = vec![];
+    /// `sq(a)` adds a * a to part 1
+    struct Square;
 
-    // Capture valid blocks
-    let mut valid_blocks: String = "".to_string();
+    impl Instruction for Square {
+        fn name(&self) -> &str {
+            "sq"
+        }
 
-    // First the first block
-    for (_, [first_block]) in first_block_re.captures_iter(&input).map(|c| c.extract()) {
-        valid_blocks += first_block;
+        fn arity(&self) -> usize {
+            1
+        }
+
+        fn apply(&self, args: &[i64], state: &mut State) -> Result<(), String> {
+            state.part1 += args[0] * args[0];
+            Ok(())
+        }
     }
 
-    // Get all the other blocks
-    for (_, [block]) in valid_block_re.captures_iter(&input).map(|c| c.extract()) {
-        valid_blocks += block;
+    /// `neg(-a)` takes a off part 1, only negative arguments are accepted
+    struct Negate;
+
+    impl Instruction for Negate {
+        fn name(&self) -> &str {
+            "neg"
+        }
+
+        fn arity(&self) -> usize {
+            1
+        }
+
+        fn arg_byte(&self, b: u8) -> bool {
+            b.is_ascii_digit() || b == b'-'
+        }
+
+        fn accepts(&self, arg: &[u8]) -> bool {
+            arg.first() == Some(&b'-')
+        }
+
+        fn apply(&self, args: &[i64], state: &mut State) -> Result<(), String> {
+            state.part1 += args[0];
+            Ok(())
+        }
     }
 
-    // Do the multiplication for the valid blocks
-    for (_, [j]) in mul_re.captures_iter(&valid_blocks).map(|c2| c2.extract()) {
-        let mul = do_multiplications(j);
-        result.push(mul)
+    #[test]
+    fn test_custom_instructions() {
+        let registry = Registry::puzzle().register(Square).register(Negate);
+        let memory = "sq(3)mul(2,2)sq(4,1)neg(-5)neg(5)dont()";
+        let mut state = State::default();
+        let (tokens, _) = registry.tokenize(memory.as_bytes());
+        for token in &tokens {
+            registry.run(token, &mut state).unwrap();
+        }
+        let names: Vec<&str> = tokens
+            .iter()
+            .map(|t| registry.instructions[t.instruction].name())
+            .collect();
+        assert_eq!(names, ["sq", "mul", "neg"]);
+        assert_eq!(state.part1, 9 + 4 - 5);
+        assert!(state.enabled);
+    }
+
+    #[test]
+    fn test_near_misses() {
+        let memory = "mul(4*mul ( 2 , 4 )mul(1234,5)mul(12,345)don't_do()mul(,1)";
+        let (tokens, near_misses) = Registry::puzzle().tokenize(memory.as_bytes());
+        assert_eq!(tokens.len(), 2);
+        // `mul (` and `don't_` never opened an instruction
+        let near_misses: Vec<(usize, &str, &str)> = near_misses
+            .iter()
+            .map(|m| (m.offset, m.text.as_str(), m.reason.as_str()))
+            .collect();
+        assert_eq!(
+            near_misses,
+            [
+                (0, "mul(4*", r#"expected ",""#),
+                (19, "mul(1234", r#"argument "1234" rejected"#),
+                (51, "mul(,", "expected an argument"),
+            ]
+        );
+        let (_, near_misses) = Registry::puzzle().tokenize(b"undo done don't_ mulch do(x");
+        assert_eq!(near_misses.len(), 1);
+        assert_eq!(near_misses[0].text, "do(x");
+    }
+
+    #[test]
+    fn test_overflow() {
+        let registry = Registry::puzzle();
+        let (tokens, _) = registry.tokenize(b"xxmul(999,999)");
+        let mut state = State {
+            part1: i64::MAX - 1,
+            ..State::default()
+        };
+        assert_eq!(
+            registry.run(&tokens[0], &mut state),
+            Err("offset 2: sum overflows i64".to_string())
+        );
+    }
+
+    /// Gives at most `n` bytes per read, to cut instructions everywhere
+    struct Trickle<'a>(&'a [u8], usize);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.1.min(buf.len()).min(self.0.len());
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
     }
 
-    let sum: i32 = result.iter().sum();
-    println!("enabled mul sum: {:?}", sum);
+    #[test]
+    fn test_stream_boundaries() {
+        let options = aoc2024::gen::Options {
+            seed: 3,
+            size: 400,
+            solvable: 50,
+        };
+        let memory = aoc2024::gen::generate(3, &options).unwrap();
+        let registry = Registry::puzzle();
+        let (tokens, near_misses) = registry.tokenize(memory.as_bytes());
+        let mut expected: Vec<Scanned> = tokens.into_iter().map(Scanned::Token).collect();
+        expected.extend(near_misses.into_iter().map(Scanned::NearMiss));
+        expected.sort_by_key(|s| match s {
+            Scanned::Token(t) => t.offset,
+            Scanned::NearMiss(m) => m.offset,
+        });
+
+        for (buffer_size, trickle) in [(16, 16), (17, 3), (23, 1), (64, 7), (4096, 4096)] {
+            let mut scanned = vec![];
+            registry
+                .scan(Trickle(memory.as_bytes(), trickle), buffer_size, |s| {
+                    scanned.push(s)
+                })
+                .unwrap();
+            assert_eq!(scanned, expected, "buffer of {}", buffer_size);
+        }
+        assert_eq!(
+            solve_stream(Trickle(memory.as_bytes(), 5), 16, |_| {}),
+            solve(memory.as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_annotate() {
+        let memory = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        assert_eq!(
+            annotate(memory.as_bytes(), false),
+            "xmul(2,4)[=8]&mul[3,7]!^{enabled subtotal: 8}don't()\
+             _mul(5,5)[=25]+mul(32,64](mul(11,8)[=88]un{disabled subtotal: 113}do()\
+             ?mul(8,5)[=40]){enabled subtotal: 40}"
+        );
+
+        let colored = annotate(b"don't()?mul(1,2)do()", true);
+        assert!(colored.contains(&Color::Dim.paint("?")));
+        assert!(Color::Dim.paint("?").starts_with("\x1b[2m"));
+        assert!(colored.contains(&Color::Red.paint("mul(1,2)")));
+        assert!(colored.ends_with(&Color::Cyan.paint("do()")));
+    }
+
+    #[test]
+    fn test_binary_memory() {
+        let memory = b"\xffmul(2,3)\xc3(\x00don't()\xe2\x82mul(4,5)\x80do()mul(1\xff,1)mul(1,1)";
+        assert_eq!(solve(memory), Ok((27, 7)));
+        let (_, near_misses) = Registry::puzzle().tokenize(memory);
+        assert_eq!(near_misses[0].text, "mul(1\u{fffd}");
+        assert_eq!(
+            annotate(b"\xffmul(2,3)", false),
+            "\u{fffd}mul(2,3)[=6]{enabled subtotal: 6}"
+        );
+    }
 }
@@ -1,23 +1,326 @@
 // https://adventofcode.com/2024/day/4
 //
 // Part 1
-// - read the file into an `Item` struct
-// - convert the Vector of Item into a hashmap
-// - look into the hashmap for the letter at the relative locations
-// - check if they spell `XMAS`
+// - read the file into a `Board`, one contiguous buffer of bytes
+// - for every letter keep a bitmask per row, 64 columns per `u64`
+// - `XMAS` starts where the X mask, the M mask one step further, the A mask
+//   two steps further and the S mask three steps further all have a bit set
+// - a step to the side is a shift of the masks, a step up or down is the
+//   mask of another row: 4 ANDs give 64 cells at once, in each of 8 directions
 // Part 2
-// - similar to part 1 look for letters at relative locations (diagonals)
-// - check if they spell the pattern (the position is important)
+// - the arm of a cross reads `MAS` either way, centered on the A
+// - that is the word read from one step before the center, in each
+//   direction of the arm, OR-ed together
+// - an X is two arms on the diagonals, AND-ed; a plus uses the row and column
+// Variants
+// - `--window ROW,COL,HEIGHT,WIDTH` crops the board before searching
+// - `--torus` wraps rows modulo the height, and columns by OR-ing the 64 bits
+//   read at `col`, `col - width`, `col - 2 * width`... of the same row
 
-use std::io::Read;
+use aoc2024::render::{Color, Overlay, Renderer};
+use aoc2024::wordsearch::Direction;
+use aoc2024::{flag, option};
+use std::{collections::HashMap, io::Read};
 
-#[derive(Debug, Clone)]
-struct Item {
-    loc: [i32; 2],
-    letter: char,
+struct Board {
+    width: usize,
+    height: usize,
+    /// Row after row, short rows are padded with 0
+    cells: Vec<u8>,
+    /// `u64`s in the mask of a row
+    words: usize,
+    /// For every letter, the mask of each row one after the other
+    masks: HashMap<u8, Vec<u64>>,
+    /// Words wrap around the edges
+    wrap: bool,
 }
 
-fn read_file(input_f: &str) -> Vec<Item> {
+impl Board {
+    fn parse(s: &str) -> Board {
+        let mut rows: Vec<&[u8]> = s
+            .split('\n')
+            .map(|l| l.strip_suffix('\r').unwrap_or(l).as_bytes())
+            .collect();
+        // Blank lines inside the grid are rows too, only the end is trimmed
+        while rows.last().is_some_and(|l| l.is_empty()) {
+            rows.pop();
+        }
+        Board::from_rows(&rows)
+    }
+
+    fn from_rows(rows: &[&[u8]]) -> Board {
+        let height = rows.len();
+        let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        let words = width.div_ceil(64);
+
+        let mut cells = vec![0; width * height];
+        let mut masks: HashMap<u8, Vec<u64>> = HashMap::new();
+        for (row, letters) in rows.iter().enumerate() {
+            cells[row * width..row * width + letters.len()].copy_from_slice(letters);
+            for (col, &letter) in letters.iter().enumerate() {
+                let mask = masks
+                    .entry(letter)
+                    .or_insert_with(|| vec![0; words * height]);
+                mask[row * words + col / 64] |= 1 << (col % 64);
+            }
+        }
+        Board {
+            width,
+            height,
+            cells,
+            words,
+            masks,
+            wrap: false,
+        }
+    }
+
+    /// The board seen as a torus
+    fn torus(mut self) -> Board {
+        self.wrap = true;
+        self
+    }
+
+    /// The `height` x `width` rectangle from (`row`, `col`), clipped to the board
+    fn crop(&self, row: usize, col: usize, height: usize, width: usize) -> Board {
+        let rows: Vec<&[u8]> = self
+            .cells
+            .chunks(self.width.max(1))
+            .skip(row)
+            .take(height)
+            .map(|r| {
+                let r = &r[col.min(r.len())..(col.saturating_add(width)).min(r.len())];
+                let len = r.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+                &r[..len]
+            })
+            .collect();
+        // Keep the empty rows, `from_rows` does not filter
+        Board {
+            wrap: self.wrap,
+            ..Board::from_rows(&rows)
+        }
+    }
+
+    /// The cell at (`row`, `col`), wrapped around on a torus
+    fn at(&self, row: isize, col: isize) -> Option<(usize, usize)> {
+        if self.wrap {
+            if self.width == 0 || self.height == 0 {
+                return None;
+            }
+            let row = row.rem_euclid(self.height as isize);
+            let col = col.rem_euclid(self.width as isize);
+            return Some((row as usize, col as usize));
+        }
+        let (row, col) = (usize::try_from(row).ok()?, usize::try_from(col).ok()?);
+        (row < self.height && col < self.width).then_some((row, col))
+    }
+
+    /// The letters as a grid of chars, for stencils and rendering
+    fn grid(&self) -> Vec<Vec<char>> {
+        self.cells
+            .chunks(self.width.max(1))
+            .map(|row| {
+                let len = row.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+                row[..len].iter().map(|&b| b as char).collect()
+            })
+            .collect()
+    }
+
+    /// Bits `64 * word + shift ..` of the `row` of `mask`, 0 outside the board
+    /// unless it wraps
+    fn shifted(&self, mask: &[u64], row: usize, word: usize, shift: isize) -> u64 {
+        let row = &mask[row * self.words..(row + 1) * self.words];
+        let get = |i: isize| {
+            usize::try_from(i)
+                .ok()
+                .and_then(|i| row.get(i))
+                .copied()
+                .unwrap_or(0)
+        };
+        let bits = |start: isize| {
+            let (i, bit) = (start.div_euclid(64), start.rem_euclid(64));
+            if bit == 0 {
+                get(i)
+            } else {
+                (get(i) >> bit) | (get(i + 1) << (64 - bit))
+            }
+        };
+        if !self.wrap {
+            return bits(word as isize * 64 + shift);
+        }
+
+        // Every copy of the row to the left of the first one read
+        let width = self.width as isize;
+        let mut start = word as isize * 64 + shift.rem_euclid(width);
+        let mut found = 0;
+        while start > -64 {
+            found |= bits(start);
+            start -= width;
+        }
+        // Only the columns of the board
+        let columns = self.width - word * 64;
+        if columns < 64 {
+            found &= (1 << columns) - 1;
+        }
+        found
+    }
+
+    /// Mask of the cells `p` such that `word` reads towards `(dr, dc)` from
+    /// `p + from * (dr, dc)`, the word must fit in the board
+    fn reads(&self, word: &[u8], (dr, dc): (isize, isize), from: isize) -> Vec<u64> {
+        let mut found = vec![0; self.height * self.words];
+        let Some(masks) = word
+            .iter()
+            .map(|l| self.masks.get(l))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return found;
+        };
+        if masks.is_empty() {
+            return found;
+        }
+
+        for row in 0..self.height {
+            // Rows of the other letters
+            let Some(rows) = (0..word.len() as isize)
+                .map(|k| self.at(row as isize + (from + k) * dr, 0))
+                .map(|cell| cell.map(|(r, _)| r))
+                .collect::<Option<Vec<usize>>>()
+            else {
+                continue;
+            };
+            for w in 0..self.words {
+                let mut bits = !0;
+                for (k, (mask, &r)) in masks.iter().zip(&rows).enumerate() {
+                    bits &= self.shifted(mask, r, w, (from + k as isize) * dc);
+                    if bits == 0 {
+                        break;
+                    }
+                }
+                found[row * self.words + w] = bits;
+            }
+        }
+        found
+    }
+
+    /// (row, col) of every bit set in `mask`
+    fn positions(&self, mask: &[u64]) -> Vec<(usize, usize)> {
+        let mut positions = vec![];
+        for (i, &bits) in mask.iter().enumerate() {
+            let (row, w) = (i / self.words, i % self.words);
+            for bit in (0..64).filter(|b| bits & (1 << b) != 0) {
+                positions.push((row, w * 64 + bit));
+            }
+        }
+        positions
+    }
+
+    /// Occurrences of `word` in all 8 directions
+    fn count(&self, word: &str) -> usize {
+        Direction::ALL
+            .iter()
+            .flat_map(|d| self.reads(word.as_bytes(), d.offset(), 0))
+            .map(|bits| bits.count_ones() as usize)
+            .sum()
+    }
+
+    /// Every cell of every occurrence of `word`
+    fn cells_of(&self, word: &str) -> Vec<(usize, usize)> {
+        let mut cells = vec![];
+        for d in Direction::ALL {
+            let (dr, dc) = d.offset();
+            for (row, col) in self.positions(&self.reads(word.as_bytes(), (dr, dc), 0)) {
+                cells.extend(
+                    (0..word.len() as isize)
+                        .filter_map(|k| self.at(row as isize + k * dr, col as isize + k * dc)),
+                );
+            }
+        }
+        cells
+    }
+
+    /// Centers of the crosses of `word`, each arm read forwards or backwards
+    fn crosses(&self, word: &str, style: Cross) -> Result<Vec<(usize, usize)>, String> {
+        if word.len().is_multiple_of(2) {
+            return Err(format!("{:?} has no middle letter", word));
+        }
+        let forwards = word.as_bytes();
+        let backwards: Vec<u8> = forwards.iter().rev().copied().collect();
+        let half = (word.len() / 2) as isize;
+        // Centers of the word read along `d`, either way
+        let arm = |d: Direction| {
+            let a = self.reads(forwards, d.offset(), -half);
+            let b = self.reads(&backwards, d.offset(), -half);
+            a.iter().zip(&b).map(|(a, b)| a | b).collect::<Vec<u64>>()
+        };
+        let both = |a: Vec<u64>, b: Vec<u64>| -> Vec<u64> {
+            a.iter().zip(&b).map(|(a, b)| a & b).collect()
+        };
+
+        let [a, b] = Cross::X.arms();
+        let x = || both(arm(a), arm(b));
+        let [a, b] = Cross::Plus.arms();
+        let plus = || both(arm(a), arm(b));
+        let centers = match style {
+            Cross::X => x(),
+            Cross::Plus => plus(),
+            Cross::Both => x().iter().zip(&plus()).map(|(a, b)| a | b).collect(),
+        };
+        Ok(self.positions(&centers))
+    }
+
+    /// Cells of the arms of the `style` cross of `len` letters around `center`
+    fn cross_cells(
+        &self,
+        (row, col): (usize, usize),
+        len: usize,
+        style: Cross,
+    ) -> Vec<(usize, usize)> {
+        let arms = match style {
+            Cross::Both => [Cross::X.arms(), Cross::Plus.arms()].concat(),
+            style => style.arms().to_vec(),
+        };
+        let half = (len / 2) as isize;
+        arms.iter()
+            .flat_map(|d| {
+                let (dr, dc) = d.offset();
+                (-half..=half).map(move |k| (row as isize + k * dr, col as isize + k * dc))
+            })
+            .filter_map(|(r, c)| self.at(r, c))
+            .collect()
+    }
+}
+
+/// Which lines through the center a cross is made of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cross {
+    /// The two diagonals
+    X,
+    /// The row and the column
+    Plus,
+    /// Either of them, a center is counted once even if it has both
+    Both,
+}
+
+impl Cross {
+    fn parse(s: &str) -> Option<Cross> {
+        match s {
+            "x" => Some(Cross::X),
+            "plus" => Some(Cross::Plus),
+            "both" => Some(Cross::Both),
+            _ => None,
+        }
+    }
+
+    /// Directions of the two arms, the plus of `Both`
+    fn arms(&self) -> [Direction; 2] {
+        match self {
+            Cross::X => [Direction::DownRight, Direction::DownLeft],
+            Cross::Plus | Cross::Both => [Direction::Right, Direction::Down],
+        }
+    }
+}
+
+fn read_file(input_f: &str) -> Board {
     let input_f = std::path::Path::new(input_f);
     let mut file: std::fs::File = match std::fs::File::open(input_f) {
         Ok(file) => file,
@@ -25,118 +328,263 @@ fn read_file(input_f: &str) -> Vec<Item> {
     };
 
     let mut s = String::new();
-    let mut items: Vec<Item> = vec![];
     match file.read_to_string(&mut s) {
-        Ok(_) => {
-            let lines: Vec<&str> = s.split("\n").collect();
-            lines.iter().enumerate().for_each(|(i, row)| {
-                row.chars()
-                    .collect::<Vec<char>>()
-                    .iter()
-                    .enumerate()
-                    .for_each(|(j, letter)| {
-                        items.push(Item {
-                            loc: [i as i32, j as i32],
-                            letter: *letter,
-                        })
-                    });
-            });
-        }
+        Ok(_) => Board::parse(&s),
         Err(e) => panic!("{:?}", e),
-    };
-    items
+    }
 }
 
-fn is_xmas(sequence: &[char]) -> bool {
-    sequence.len() == 4
-        && sequence[0] == 'X'
-        && sequence[1] == 'M'
-        && sequence[2] == 'A'
-        && sequence[3] == 'S'
+fn count_xmas(board: &Board) -> usize {
+    board.count("XMAS")
 }
-fn check_diagonal(items: [&Item; 3]) -> bool {
-    let first = items[0].letter;
-    let second = items[1].letter;
-    let third = items[2].letter;
-    (first == 'M' && second == 'A' && third == 'S')
-        || (first == 'S' && second == 'A' && third == 'M')
+
+/// Draw the grid with the letters of every match highlighted
+fn print_matches(board: &Board, word: &str, style: Cross, centers: &[(usize, usize)]) {
+    let grid = board.grid();
+    let xmas_cells = board.cells_of("XMAS");
+    let cross_cells = centers
+        .iter()
+        .flat_map(|&c| board.cross_cells(c, word.len(), style));
+    let center_glyphs = centers.iter().map(|&c| (c, '+'));
+
+    Renderer::new(&grid)
+        .overlay(Overlay::new("XMAS", Color::Cyan).cells(xmas_cells))
+        .overlay(Overlay::new(format!("{} cross", word), Color::Magenta).cells(cross_cells))
+        .overlay(Overlay::new("cross center", Color::Yellow).glyphs(center_glyphs))
+        .print();
 }
 
 fn main() {
-    let inp = read_file("inputs/day04.txt");
+    let mut board = read_file("inputs/day04.txt");
+    if let Some(window) = option("--window") {
+        let Ok([row, col, height, width]) = <[usize; 4]>::try_from(
+            window
+                .split(',')
+                .map(|n| n.trim().parse().expect("window is ROW,COL,HEIGHT,WIDTH"))
+                .collect::<Vec<usize>>(),
+        ) else {
+            panic!("window is ROW,COL,HEIGHT,WIDTH, not {:?}", window);
+        };
+        board = board.crop(row, col, height, width);
+    }
+    if flag("--torus") {
+        board = board.torus();
+    }
 
     // Part 1 - look form `XMAS` in horizontal, vertical, diagonal (both ways)
-    let directions = [
-        // Horizontal: (row change, col change)
-        (0, 1),  // right
-        (0, -1), // left
-        // Vertical
-        (1, 0),  // down
-        (-1, 0), // up
-        // Diagonal top-left to bottom-right
-        (1, 1),
-        (-1, -1),
-        // Diagonal top-right to bottom-left
-        (1, -1),
-        (-1, 1),
-    ];
-
-    let item_map: std::collections::HashMap<_, _> = inp
-        .clone()
-        .into_iter()
-        .map(|item| (item.loc, item))
-        .collect();
-
-    let mut xmas_counter = 0;
-    for item in &inp {
-        for &(row_delta, col_delta) in &directions {
-            let mut word = vec![item.letter];
-            let mut loc = item.loc;
-            for _ in 0..3 {
-                loc = [loc[0] + row_delta, loc[1] + col_delta];
-                if let Some(next) = item_map.get(&loc) {
-                    word.push(next.letter);
-                } else {
-                    break;
-                }
-                if word.len() == 4 && is_xmas(&word) {
-                    xmas_counter += 1;
+    let xmas_counter = count_xmas(&board);
+    println!("xmas_counter: {:?}", xmas_counter);
+
+    // Part 2 find `MAS` crossing in diagonals, or any word with
+    // `--cross-word WORD` and `--cross x|plus|both`
+    let word = option("--cross-word").unwrap_or("MAS".to_string());
+    let style = option("--cross").map_or(Cross::X, |s| {
+        Cross::parse(&s).unwrap_or_else(|| panic!("unknown cross style {:?}", s))
+    });
+    let centers = board
+        .crosses(&word, style)
+        .unwrap_or_else(|e| panic!("{}", e));
+    println!("mas_counter: {:?}", centers.len());
+
+    if flag("--render") {
+        print_matches(&board, &word, style, &centers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use aoc2024::stencil::{Stencil, Symmetry};
+    use aoc2024::wordsearch::WordSearch;
+    use proptest::prelude::*;
+
+    fn count_x_mas(board: &Board) -> usize {
+        board
+            .crosses("MAS", Cross::X)
+            .expect("MAS has a middle")
+            .len()
+    }
+
+    /// The letter at (`r`, `c`), wrapping around the longest row if `wrap`
+    fn naive_at(grid: &[Vec<char>], wrap: bool, r: isize, c: isize) -> Option<char> {
+        let width = grid.iter().map(|row| row.len()).max().unwrap_or(0) as isize;
+        let (r, c) = match wrap {
+            _ if width == 0 => return None,
+            true => (r.rem_euclid(grid.len() as isize), c.rem_euclid(width)),
+            false => (r, c),
+        };
+        grid.get(usize::try_from(r).ok()?)?
+            .get(usize::try_from(c).ok()?)
+            .copied()
+    }
+
+    /// Occurrences of `word`, checking letter by letter from every cell
+    fn naive_count(grid: &[Vec<char>], word: &str, wrap: bool) -> usize {
+        let word: Vec<char> = word.chars().collect();
+        let mut count = 0;
+        for (r, row) in grid.iter().enumerate() {
+            for c in 0..row.len() {
+                for d in Direction::ALL {
+                    let (dr, dc) = d.offset();
+                    count += (0..word.len() as isize)
+                        .map(|k| naive_at(grid, wrap, r as isize + k * dr, c as isize + k * dc))
+                        .eq(word.iter().map(|&l| Some(l))) as usize;
                 }
             }
         }
+        count
     }
-    println!("xmas_counter: {:?}", xmas_counter);
 
-    // Part 2 find `MAS` in diagonals
-    let mut mas_counter = 0;
-    for item in inp.iter() {
-        if item.letter == 'A' {
-            let locations = [
-                [item.loc[0] - 1, item.loc[1] - 1], // top-left
-                [item.loc[0] + 1, item.loc[1] + 1], // bottom-right
-                [item.loc[0] - 1, item.loc[1] + 1], // top-right
-                [item.loc[0] + 1, item.loc[1] - 1], // bottom-left
-            ];
-
-            // Get items from the map corresponding to the diagonal locations
-            let top_left = item_map.get(&locations[0]);
-            let bottom_right = item_map.get(&locations[1]);
-            let top_right = item_map.get(&locations[2]);
-            let bottom_left = item_map.get(&locations[3]);
-
-            // Check if we have a valid X-MAS pattern
-            if let (Some(tl), Some(br), Some(tr), Some(bl)) =
-                (top_left, bottom_right, top_right, bottom_left)
-            {
-                // First diagonal: check top-left and bottom-right
-                let diagonal1 = [tl, item, br];
-                let diagonal2 = [tr, item, bl];
-
-                if check_diagonal(diagonal1) && check_diagonal(diagonal2) {
-                    mas_counter += 1;
+    /// Centers of crosses, checking every arm letter by letter
+    fn naive_crosses(
+        grid: &[Vec<char>],
+        word: &str,
+        style: Cross,
+        wrap: bool,
+    ) -> Vec<(usize, usize)> {
+        let word: Vec<char> = word.chars().collect();
+        let half = (word.len() / 2) as isize;
+        let at = |r: isize, c: isize| naive_at(grid, wrap, r, c);
+        let arm = |r: isize, c: isize, (dr, dc): (isize, isize)| {
+            let line: Option<Vec<char>> =
+                (-half..=half).map(|k| at(r + k * dr, c + k * dc)).collect();
+            line.is_some_and(|l| l == word || l.iter().rev().eq(word.iter()))
+        };
+        let mut centers = vec![];
+        for (r, row) in grid.iter().enumerate() {
+            for c in 0..row.len() {
+                let (r, c) = (r as isize, c as isize);
+                let x = arm(r, c, (1, 1)) && arm(r, c, (1, -1));
+                let plus = arm(r, c, (0, 1)) && arm(r, c, (1, 0));
+                let found = match style {
+                    Cross::X => x,
+                    Cross::Plus => plus,
+                    Cross::Both => x || plus,
+                };
+                if found {
+                    centers.push((r as usize, c as usize));
                 }
             }
         }
+        centers
+    }
+
+    #[test]
+    fn test_crosses() {
+        let board = Board::parse("S.S.S\n.AAA.\nSAMAS\n.AAA.\nM.S.M\n");
+        assert_eq!(board.crosses("SAM", Cross::X), Ok(vec![]));
+        assert_eq!(board.crosses("AMA", Cross::X), Ok(vec![(2, 2)]));
+        assert_eq!(board.crosses("AMA", Cross::Plus), Ok(vec![(2, 2)]));
+        assert_eq!(board.crosses("SAMAS", Cross::Plus), Ok(vec![(2, 2)]));
+        assert_eq!(board.crosses("SAMAS", Cross::X), Ok(vec![]));
+        assert_eq!(board.crosses("A", Cross::Both).unwrap().len(), 8);
+        assert!(board.crosses("AM", Cross::X).is_err());
+        assert_eq!(
+            board.cross_cells((0, 1), 3, Cross::X),
+            [(0, 1), (1, 2), (0, 1), (1, 0)]
+        );
+    }
+
+    #[test]
+    fn test_blank_rows() {
+        let board = Board::parse("XMAS\n\nSAMX\n\n\n");
+        assert_eq!(board.height, 3);
+        assert_eq!(
+            board.grid(),
+            [vec!['X', 'M', 'A', 'S'], vec![], vec!['S', 'A', 'M', 'X']]
+        );
+        assert_eq!(board.count("XMAS"), 2);
+        // Not joined into one grid, the letters are two rows apart
+        assert_eq!(board.count("XS"), 0);
+        assert_eq!(Board::parse("XMAS\nSAMX\n").count("XS"), 2);
+    }
+
+    #[test]
+    fn test_torus_and_window() {
+        let board = Board::parse("MASX\n....\nA...\nM...\n");
+        assert_eq!(board.count("XMAS"), 0);
+        let torus = Board::parse("MASX\n....\nA...\nM...\n").torus();
+        assert_eq!(torus.count("XMAS"), 1);
+        assert_eq!(torus.count("AMX"), 1);
+        assert_eq!(torus.cells_of("XMAS"), [(0, 3), (0, 0), (0, 1), (0, 2)]);
+
+        let window = board.crop(2, 0, 5, 1);
+        assert_eq!(window.grid(), [['A'], ['M']]);
+        // One column wide, the diagonals wrap onto the column too
+        assert_eq!(window.torus().count("AMA"), 6);
+    }
+
+    #[test]
+    fn test_example_data() {
+        let board = read_file("inputs/example_day04.txt");
+
+        assert_eq!(count_xmas(&board), 18);
+        assert_eq!(count_x_mas(&board), 9);
+        assert_eq!(board.cells_of("XMAS").len(), 18 * 4);
+    }
+
+    proptest! {
+        // Wide rows, so words cross from one u64 to the next
+        #[test]
+        fn test_count_matches_wordsearch(
+            rows in prop::collection::vec("[XMAS]{1,150}", 1..6),
+            word in "[XMAS]{1,4}",
+        ) {
+            let board = Board::parse(&rows.join("\n"));
+            let grid: Vec<Vec<char>> = rows.iter().map(|r| r.chars().collect()).collect();
+            prop_assert_eq!(board.grid(), grid.clone());
+            prop_assert_eq!(board.count(&word), WordSearch::new([word.as_str()]).find(&grid).len());
+        }
+
+        #[test]
+        fn test_crosses_match_naive(
+            rows in prop::collection::vec("[MAS]{1,70}", 1..8),
+            word in "[MAS]{1,2}[MAS]",
+            style in prop::sample::select(vec![Cross::X, Cross::Plus, Cross::Both]),
+        ) {
+            let word = if word.len().is_multiple_of(2) { &word[1..] } else { &word[..] };
+            let board = Board::parse(&rows.join("\n"));
+            prop_assert_eq!(board.crosses(word, style), Ok(naive_crosses(&board.grid(), word, style, false)));
+        }
+
+        #[test]
+        fn test_torus_matches_naive(
+            rows in prop::collection::vec("[XMAS]{1,70}", 1..6),
+            word in "[XMAS]{1,4}",
+            style in prop::sample::select(vec![Cross::X, Cross::Plus, Cross::Both]),
+        ) {
+            let board = Board::parse(&rows.join("\n")).torus();
+            let grid = board.grid();
+            prop_assert_eq!(board.count(&word), naive_count(&grid, &word, true));
+            let cross = &word[..word.len() - 1 + word.len() % 2];
+            prop_assert_eq!(board.crosses(cross, style), Ok(naive_crosses(&grid, cross, style, true)));
+        }
+
+        #[test]
+        fn test_crop_matches_subgrid(
+            rows in prop::collection::vec("[XMAS]{1,100}", 1..8),
+            (row, col, height, width) in (0..10usize, 0..120usize, 0..10usize, 0..120usize),
+            torus: bool,
+        ) {
+            let board = Board::parse(&rows.join("\n"));
+            let board = if torus { board.torus() } else { board };
+            let window = board.crop(row, col, height, width);
+            let sub: Vec<Vec<char>> = rows
+                .iter()
+                .skip(row)
+                .take(height)
+                .map(|r| r.chars().skip(col).take(width).collect())
+                .collect();
+            prop_assert_eq!(window.count("XMAS"), naive_count(&sub, "XMAS", torus));
+        }
+
+        #[test]
+        fn test_x_mas_matches_stencil(rows in prop::collection::vec("[MAS]{12}", 1..12)) {
+            let board = Board::parse(&rows.join("\n"));
+            let stencil: Stencil = "M.S/.A./M.S".parse().unwrap();
+            let expected = stencil.find(&board.grid(), Symmetry::Rotations).len();
+            prop_assert_eq!(count_x_mas(&board), expected);
+        }
     }
-    println!("mas_counter: {:?}", mas_counter);
 }
@@ -5,47 +5,47 @@ use std::{
     io::Read,
 };
 
-fn load_data(input_f: &str) -> (HashMap<usize, HashSet<usize>>, Vec<Vec<usize>>) {
+type Rules = HashMap<usize, HashSet<usize>>;
+
+fn parse_page(page: &str) -> Result<usize, String> {
+    page.parse::<u32>()
+        .map(|p| p as usize)
+        .map_err(|e| format!("invalid page {:?}: {}", page, e))
+}
+
+fn parse_data(s: &str) -> Result<(Rules, Vec<Vec<usize>>), String> {
+    let mut rules: Rules = HashMap::new();
+    let mut updates: Vec<Vec<usize>> = vec![];
+    for l in s.split("\n") {
+        if let Some((k, v)) = l.split_once('|') {
+            rules
+                .entry(parse_page(k)?)
+                .or_default()
+                .insert(parse_page(v)?);
+        } else if !l.is_empty() {
+            let u: Vec<usize> = l.split(",").map(parse_page).collect::<Result<_, _>>()?;
+            updates.push(u);
+        }
+    }
+    Ok((rules, updates))
+}
+
+fn load_data(input_f: &str) -> (Rules, Vec<Vec<usize>>) {
     let mut file = read_input_to_file(input_f);
 
     let mut s = String::new();
-    let mut rules: HashMap<usize, HashSet<usize>> = HashMap::new();
-    let mut updates: Vec<Vec<usize>> = vec![];
     match file.read_to_string(&mut s) {
-        Ok(_) => {
-            let lines: Vec<&str> = s.split("\n").collect();
-            lines.iter().for_each(|l| {
-                if l.contains('|') {
-                    let _l: Vec<&str> = l.split('|').collect();
-                    let k: usize = _l[0].parse().unwrap();
-                    let v: usize = _l[1].parse().unwrap();
-                    rules.entry(k).or_default().insert(v);
-                } else if !l.is_empty() {
-                    let u: Vec<usize> = l.split(",").map(|x| x.parse::<usize>().unwrap()).collect();
-                    updates.push(u);
-                }
-            });
-        }
+        Ok(_) => parse_data(&s).unwrap_or_else(|e| panic!("{}", e)),
         Err(e) => panic!("{:?}", e),
-    };
-    (rules, updates)
+    }
 }
 
-fn is_manual_valid(manual: &[usize], rules: &HashMap<usize, HashSet<usize>>) -> bool {
+/// No page is printed after one that must come later, pages without a rule
+/// between them can come in any order
+fn is_manual_valid(manual: &[usize], rules: &Rules) -> bool {
     let mut valid_pages: Vec<bool> = vec![];
     manual.iter().enumerate().for_each(|(i, page)| {
         let behind = &manual[..i];
-        let ahead = &manual[i + 1..];
-        for page_ahead in ahead {
-            // println!("checking page ahead {:?}", page_ahead);
-            if let Some(p) = rules.get(page) {
-                if p.contains(page_ahead) {
-                    valid_pages.push(true);
-                } else {
-                    valid_pages.push(false);
-                }
-            }
-        }
 
         // check if pages behind are allowed
         for page_behind in behind {
@@ -61,7 +61,7 @@ fn is_manual_valid(manual: &[usize], rules: &HashMap<usize, HashSet<usize>>) ->
     valid_pages.iter().all(|x| *x)
 }
 
-fn topological_sort(manual: &[usize], rules: &HashMap<usize, HashSet<usize>>) -> Vec<usize> {
+fn topological_sort(manual: &[usize], rules: &Rules) -> Vec<usize> {
     let mut sorted = Vec::new();
     let mut remaining: HashSet<usize> = manual.iter().cloned().collect();
 
@@ -70,10 +70,7 @@ fn topological_sort(manual: &[usize], rules: &HashMap<usize, HashSet<usize>>) ->
         if let Some(next_page) = remaining.clone().iter().find(|&page| {
             // Check if this page can be placed without violating any rules
             !remaining.iter().any(|&other| {
-                other != *page
-                    && rules
-                        .get(&other)
-                        .map_or(false, |after| after.contains(page))
+                other != *page && rules.get(&other).is_some_and(|after| after.contains(page))
             })
         }) {
             sorted.push(*next_page);
@@ -90,7 +87,7 @@ fn topological_sort(manual: &[usize], rules: &HashMap<usize, HashSet<usize>>) ->
     sorted
 }
 
-fn fix_incorrect_manual(manual: &[usize], rules: &HashMap<usize, HashSet<usize>>) -> Vec<usize> {
+fn fix_incorrect_manual(manual: &[usize], rules: &Rules) -> Vec<usize> {
     // Try topological sort first
     let sorted = topological_sort(manual, rules);
 
@@ -104,15 +101,12 @@ fn fix_incorrect_manual(manual: &[usize], rules: &HashMap<usize, HashSet<usize>>
     manual.to_vec()
 }
 
-fn main() {
-    let (rules, updates) = load_data("inputs/day05.txt");
-    // let (rules, updates) = load_data("inputs/example_day05.txt");
-
+fn solve(rules: &Rules, updates: &[Vec<usize>]) -> (usize, usize) {
     // Part 1
     let mut result: Vec<usize> = vec![];
     let mut incorrect_manuals = vec![];
     updates.iter().for_each(|manual| {
-        if is_manual_valid(manual, &rules) {
+        if is_manual_valid(manual, rules) {
             // println!("✅ {:?}", manual);
             // get the middle number
             result.push(manual[manual.len() / 2])
@@ -121,20 +115,118 @@ fn main() {
             // println!("❌ {:?}", manual)
         }
     });
-    println!("result {:?}", result.iter().sum::<usize>());
+    let part1 = result.iter().sum::<usize>();
 
     // Part 2
     let mut result: Vec<usize> = vec![];
     incorrect_manuals.iter().for_each(|manual| {
-        if is_manual_valid(manual, &rules) {
+        if is_manual_valid(manual, rules) {
             // Correctly ordered update, use existing middle page
             result.push(manual[manual.len() / 2])
         } else {
             // Incorrect update, find correct ordering
-            let corrected_manual = fix_incorrect_manual(manual, &rules);
+            let corrected_manual = fix_incorrect_manual(manual, rules);
             result.push(corrected_manual[corrected_manual.len() / 2]);
         }
     });
+    let part2 = result.iter().sum::<usize>();
+
+    (part1, part2)
+}
 
-    println!("correct result: {:?}", result.iter().sum::<usize>());
+fn main() {
+    let (rules, updates) = load_data("inputs/day05.txt");
+    // let (rules, updates) = load_data("inputs/example_day05.txt");
+
+    let (part1, part2) = solve(&rules, &updates);
+    println!("result {:?}", part1);
+    println!("correct result: {:?}", part2);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use itertools::Itertools;
+    use proptest::prelude::*;
+
+    // A rule for the pairs of pages picked by `keep`, following their order
+    // in `order`, so there is never a cycle
+    fn rules_in_order(order: &[usize], keep: &[bool]) -> Rules {
+        let mut rules: Rules = HashMap::new();
+        let pairs = order
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &a)| order[i + 1..].iter().map(move |&b| (a, b)));
+        for ((a, b), &keep) in pairs.zip(keep) {
+            if keep {
+                rules.entry(a).or_default().insert(b);
+            }
+        }
+        rules
+    }
+
+    fn naive_is_valid(manual: &[usize], rules: &Rules) -> bool {
+        manual.iter().enumerate().all(|(i, a)| {
+            manual[i + 1..]
+                .iter()
+                .all(|b| !rules.get(b).is_some_and(|after| after.contains(a)))
+        })
+    }
+
+    // Try every ordering until one breaks no rule
+    fn naive_sort(manual: &[usize], rules: &Rules) -> Option<Vec<usize>> {
+        manual
+            .iter()
+            .copied()
+            .permutations(manual.len())
+            .find(|p| naive_is_valid(p, rules))
+    }
+
+    // Acyclic rules over distinct pages, some of the pairs only, and a
+    // manual with some of the pages
+    fn rules_and_manual() -> impl Strategy<Value = (Rules, Vec<usize>)> {
+        prop::sample::subsequence((10..40).collect::<Vec<usize>>(), 2..9)
+            .prop_shuffle()
+            .prop_flat_map(|order| {
+                let len = order.len();
+                let manual = prop::sample::subsequence(order.clone(), 1..=len).prop_shuffle();
+                let keep = prop::collection::vec(any::<bool>(), len * (len - 1) / 2);
+                (Just(order), keep, manual)
+            })
+            .prop_map(|(order, keep, manual)| (rules_in_order(&order, &keep), manual))
+    }
+
+    #[test]
+    fn test_example_data() {
+        let (rules, updates) = load_data("inputs/example_day05.txt");
+
+        assert_eq!(solve(&rules, &updates), (143, 123));
+        assert!(parse_data("1|").is_err());
+    }
+
+    #[test]
+    fn test_partial_rules() {
+        let rules = HashMap::from([(1, HashSet::from([3]))]);
+        assert!(is_manual_valid(&[1, 2], &rules));
+        assert!(is_manual_valid(&[2, 1, 3], &rules));
+        assert!(!is_manual_valid(&[3, 2, 1], &rules));
+    }
+
+    proptest! {
+        #[test]
+        fn test_is_manual_valid_matches_naive((rules, manual) in rules_and_manual()) {
+            prop_assert_eq!(is_manual_valid(&manual, &rules), naive_is_valid(&manual, &rules));
+        }
+
+        #[test]
+        fn test_topological_sort_is_valid((rules, manual) in rules_and_manual()) {
+            // Pages without a rule between them can go either way, so the
+            // orderings may differ but both must be valid
+            let sorted = topological_sort(&manual, &rules);
+            prop_assert!(naive_sort(&manual, &rules).is_some());
+            prop_assert!(naive_is_valid(&sorted, &rules));
+            prop_assert_eq!(sorted.iter().sorted().collect_vec(), manual.iter().sorted().collect_vec());
+        }
+    }
 }
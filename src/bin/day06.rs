@@ -1,161 +1,127 @@
 // https://adventofcode.com/2024/day/6
-use aoc2024::read_input_to_file;
-use std::{collections::HashSet, io::Read};
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Direction {
-    Up,
-    Right,
-    Down,
-    Left,
+use aoc2024::export::{Exporter, Frame, Palette};
+use aoc2024::geometry::Point;
+use aoc2024::lab::{Direction, Guard, Lab};
+use aoc2024::render::{Color, Overlay, Renderer};
+use aoc2024::walker::{Outcome, StepRule, Walker};
+use aoc2024::{flag, option, parallel, read_input_to_file};
+use std::{
+    collections::{HashMap, HashSet},
+    io::Read,
+    path::Path,
+};
+
+/// Every state of the patrol, until the guard leaves or first closes a loop,
+/// and whether it loops
+fn walk(guard: Guard, map: &Map) -> (Vec<Guard>, bool) {
+    let walker = Walker::new(map, guard);
+    let (len, looped) = match walker.run() {
+        Outcome::Exit { steps, .. } => (steps + 1, false),
+        Outcome::Loop { start, period, .. } => (start + period, true),
+    };
+    (walker.states().take(len).collect(), looped)
 }
 
-impl Direction {
-    fn turn_right(&self) -> Self {
-        match self {
-            Direction::Up => Direction::Right,
-            Direction::Right => Direction::Down,
-            Direction::Down => Direction::Left,
-            Direction::Left => Direction::Up,
-        }
+/// Distinct positions visited before leaving the map, `None` if the guard loops
+fn patrol(guard: Guard, map: &Map) -> Option<usize> {
+    let (states, looped) = walk(guard, map);
+    if looped {
+        return None;
     }
-}
-#[derive(Clone)]
-struct Guard {
-    x: i32,
-    y: i32,
-    direction: Direction,
+    let visited: HashSet<Point> = states.iter().map(|g| g.position).collect();
+    Some(visited.len())
 }
 
-impl Guard {
-    fn new(x: i32, y: i32, direction: Direction) -> Self {
-        Guard { x, y, direction }
-    }
-
-    fn walk(&mut self) {
-        match self.direction {
-            Direction::Up => self.y -= 1,
-            Direction::Right => self.x += 1,
-            Direction::Down => self.y += 1,
-            Direction::Left => self.x -= 1,
-        }
-    }
-
-    fn patrol(mut self, mut map: Map) -> usize {
-        let mut visited = HashSet::new();
-
-        visited.insert((self.x, self.y));
-
-        while map.within_bounds(self.x, self.y) {
-            // get the next step
-            let (next_x, next_y) = match self.direction {
-                Direction::Up => (self.x, self.y - 1),
-                Direction::Right => (self.x + 1, self.y),
-                Direction::Down => (self.x, self.y + 1),
-                Direction::Left => (self.x - 1, self.y),
-            };
-
-            // if the guard fell of the map terminate the patrol
-            if !map.within_bounds(next_x, next_y) {
-                break;
-            }
-
-            if map.get_cell(next_x, next_y) == '#' {
-                self.direction = self.direction.turn_right();
-            } else {
-                self.walk();
-                map.mark_visited(self.x, self.y);
-                visited.insert((self.x, self.y));
-            };
-            // map.print_map();
-            // println!("========================");
-            // thread::sleep(Duration::from_secs_f64(0.05));
-        }
-        visited.len()
-    }
-
-    fn is_stuck_in_loop(&mut self, map: Map) -> bool {
-        let mut state_history = HashSet::new();
-
-        while map.within_bounds(self.x, self.y) {
-            // Create a unique state representation
-            let current_state = (self.x, self.y, self.direction);
-
-            // If we've seen this exact state before, we're in a loop
-            if !state_history.insert(current_state) {
-                return true;
-            }
-
-            let (next_x, next_y) = match self.direction {
-                Direction::Up => (self.x, self.y - 1),
-                Direction::Right => (self.x + 1, self.y),
-                Direction::Down => (self.x, self.y + 1),
-                Direction::Left => (self.x - 1, self.y),
-            };
-
-            // Check if out of bounds
-            if !map.within_bounds(next_x, next_y) {
-                return false;
-            }
-
-            if map.get_cell(next_x, next_y) == '#' {
-                self.direction = self.direction.turn_right();
-            } else {
-                self.walk();
-            }
-        }
-        false
-    }
+fn is_stuck_in_loop(guard: Guard, map: &Map) -> bool {
+    Walker::new(map, guard).run().is_loop()
 }
 
 #[derive(Clone)]
 struct Map(Vec<Vec<char>>);
 
 impl Map {
+    fn parse(s: &str) -> Self {
+        let map: Vec<Vec<char>> = s
+            .trim() // Remove leading/trailing whitespace
+            .split('\n')
+            .map(|l| l.chars().collect())
+            .collect();
+        Map(map)
+    }
+
     fn load(input_f: &str) -> Self {
         let mut file = read_input_to_file(input_f);
         let mut s = String::new();
         match file.read_to_string(&mut s) {
-            Ok(_) => {
-                let map: Vec<Vec<char>> = s
-                    .trim() // Remove leading/trailing whitespace
-                    .split('\n')
-                    .map(|l| l.chars().collect())
-                    .collect();
-                let m = Map(map);
-                // m.print_map();
-                m
-            }
+            Ok(_) => Map::parse(&s),
             Err(e) => panic!("{:?}", e),
         }
     }
-    fn find_guard(&self) -> Guard {
-        for (y, row) in self.0.iter().enumerate() {
-            for (x, cell) in row.iter().enumerate() {
-                if *cell == '^' {
-                    // println!("{:?}-{:?}", x, y);
-                    return Guard::new(x as i32, y as i32, Direction::Up);
-                }
-            }
-        }
-        panic!("no guard on duty!")
+
+    fn find_guard(&self) -> Option<Guard> {
+        Lab(&self.0).find_guard()
     }
 
-    fn mark_visited(&mut self, x: i32, y: i32) {
-        self.0[y as usize][x as usize] = 'X'
+    /// The path walked through `states`, pointing where the guard was heading
+    fn path_overlays(states: &[Guard]) -> Vec<Overlay> {
+        let at = |g: &Guard| {
+            (
+                (g.position.y as usize, g.position.x as usize),
+                g.heading.glyph(),
+            )
+        };
+        let path: HashMap<(usize, usize), char> = states.iter().map(at).collect();
+
+        vec![
+            Overlay::new("path", Color::Yellow).glyphs(path),
+            Overlay::new("start", Color::Green).glyphs(states.first().map(at)),
+            Overlay::new("guard", Color::Red).glyphs(states.last().map(at)),
+        ]
     }
 
-    // fn print_map(&self) {
-    //     self.0.iter().for_each(|x| println!("{:?}", x));
-    // }
+    /// Draw the map with the path of the guard
+    fn print_map(&self, guard: Guard) {
+        let (states, looped) = walk(guard, self);
+        Renderer::new(&self.0)
+            .overlays(Map::path_overlays(&states))
+            .print();
+        if looped {
+            println!("the guard walks this loop forever");
+        }
+    }
 
-    fn get_cell(&self, x: i32, y: i32) -> char {
-        self.0[y as usize][x as usize]
+    /// Save the patrol as an image, see `--export`
+    ///
+    /// Animations get one frame every `--frame-step N` steps (by default
+    /// about 250 frames in total), the last frame shows the obstacles that
+    /// would trap the guard in a loop.
+    fn export(&self, guard: Guard, obstacles: &[Point], path: &str) {
+        let (states, _) = walk(guard, self);
+        let palette = Palette::from_args();
+        let frame_step =
+            frame_step(option("--frame-step"), states.len()).unwrap_or_else(|e| panic!("{}", e));
+
+        let mut frames: Vec<Frame> = (1..states.len())
+            .step_by(frame_step)
+            .map(|end| Frame::new(&self.0, &Map::path_overlays(&states[..end]), &palette))
+            .collect();
+        let mut last = Map::path_overlays(&states);
+        last.push(
+            Overlay::new("loop obstacles", Color::Magenta)
+                .cells(obstacles.iter().map(|p| (p.y as usize, p.x as usize))),
+        );
+        frames.push(Frame::new(&self.0, &last, &palette));
+
+        Exporter::from_args()
+            .export(Path::new(path), &frames)
+            .unwrap_or_else(|e| panic!("could not export {}: {}!", path, e));
     }
-    fn within_bounds(&self, x: i32, y: i32) -> bool {
-        x >= 0 && y >= 0 && y < self.0.len() as i32 && x < self.0[y as usize].len() as i32
+
+    fn get_cell(&self, p: Point) -> char {
+        self.0[p.y as usize][p.x as usize]
     }
-    fn time_loop(&self) -> usize {
+    /// Obstacles that trap the guard in a loop, and how many maps were checked
+    fn time_loop(&self) -> (Vec<Point>, usize) {
         //
         // Initialize simulation!
         //
@@ -168,48 +134,111 @@ impl Map {
         //  > it was just a clone, the real guard is safe. Clones don't feel anything...
         //  > hopefully
         //
-        let mut loop_positions = 0;
         // Find the original guard
-        let guard_start = self.find_guard();
-        let mut total_maps = 0;
-        for y in 0..self.0.len() {
-            for x in 0..self.0[y].len() {
-                // Skip the guard's starting position
-                if self.0[y][x] == '^' || self.0[y][x] == '#' {
-                    continue;
-                }
-                // Create a copy of the map with a new obstacle
-                let mut test_map = self.clone();
-                test_map.0[y][x] = '#';
-
-                // Create a clone of the guard to run this map
-                let mut test_guard = guard_start.clone();
-
-                // Observe if the clone is stuck
-                if test_guard.is_stuck_in_loop(test_map) {
-                    loop_positions += 1;
-                }
-
-                total_maps += 1;
-            }
-        }
-        println!(
-            "checked {:?} maps, {:?} guards are stuck",
-            total_maps, loop_positions
-        );
-        loop_positions
+        let Some(guard_start) = self.find_guard() else {
+            return (vec![], 0);
+        };
+        // Every free cell, except the guard's starting position
+        let candidates: Vec<Point> = (0..self.0.len())
+            .flat_map(|y| (0..self.0[y].len()).map(move |x| Point::new(x as i64, y as i64)))
+            .filter(|&p| !matches!(self.get_cell(p), '^' | '#'))
+            .collect();
+        let total_maps = candidates.len();
+
+        // Each clone runs on its own copy of the map, so they can all run at once
+        let loop_positions: Vec<Point> = parallel::filter(&candidates, |&p| {
+            // Create a copy of the map with a new obstacle
+            let mut test_map = self.clone();
+            test_map.0[p.y as usize][p.x as usize] = '#';
+
+            // Observe if a clone of the guard is stuck
+            is_stuck_in_loop(guard_start, &test_map)
+        })
+        .into_iter()
+        .copied()
+        .collect();
+        (loop_positions, total_maps)
+    }
+}
+
+/// Steps between two frames of an animation of `states`, from `--frame-step`
+fn frame_step(arg: Option<String>, states: usize) -> Result<usize, String> {
+    match arg.map(|n| n.parse::<usize>()) {
+        None => Ok((states / 250).max(1)),
+        Some(Ok(0)) => Err("--frame-step must be at least 1".to_string()),
+        Some(Ok(n)) => Ok(n),
+        Some(Err(e)) => Err(format!("invalid --frame-step: {}", e)),
+    }
+}
+
+impl StepRule<Direction> for Map {
+    fn step(&self, guard: &Guard) -> Option<Guard> {
+        Lab(&self.0).step(guard)
     }
 }
 
 fn main() {
+    parallel::configure();
     // let map = Map::load("inputs/example_input06.txt");
     let map = Map::load("inputs/day06.txt");
 
-    let guard = map.find_guard();
+    let guard = map.find_guard().expect("no guard on duty!");
+
+    if flag("--render") {
+        map.print_map(guard);
+    }
+
+    match patrol(guard, &map) {
+        Some(visited_locs) => println!("visited: {:?}", visited_locs),
+        None => println!("visited: none, the guard never leaves"),
+    }
+
+    let (loop_positions, total_maps) = map.time_loop();
+    println!(
+        "checked {:?} maps, {:?} guards are stuck",
+        total_maps,
+        loop_positions.len()
+    );
+    println!("Positions that cause a loop: {}", loop_positions.len());
 
-    let visited_locs = guard.patrol(map.clone());
-    println!("visited: {:?}", visited_locs);
+    if let Some(path) = option("--export") {
+        map.export(guard, &loop_positions, &path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
 
-    let loop_positions = map.time_loop();
-    println!("Positions that cause a loop: {}", loop_positions);
+    use super::*;
+
+    #[test]
+    fn test_example_data() {
+        let map = Map::load("inputs/example_input06.txt");
+        let guard = map.find_guard().unwrap();
+
+        assert_eq!(patrol(guard, &map), Some(41));
+        let (loop_positions, total_maps) = map.time_loop();
+        assert_eq!(loop_positions.len(), 6);
+        assert_eq!(total_maps, 91);
+    }
+
+    #[test]
+    fn test_patrol_loop() {
+        let map = Map::parse(".#..\n...#\n#^..\n..#.");
+        let guard = map.find_guard().unwrap();
+
+        assert_eq!(patrol(guard, &map), None);
+        let (states, looped) = walk(guard, &map);
+        assert!(looped);
+        assert_eq!(states.len(), 8);
+    }
+
+    #[test]
+    fn test_frame_step() {
+        assert_eq!(frame_step(None, 1000), Ok(4));
+        assert_eq!(frame_step(None, 10), Ok(1));
+        assert_eq!(frame_step(Some("3".to_string()), 10), Ok(3));
+        assert!(frame_step(Some("0".to_string()), 10).is_err());
+        assert!(frame_step(Some("x".to_string()), 10).is_err());
+    }
 }
@@ -1,7 +1,8 @@
 // https://adventofcode.com/2024/day/7
+use aoc2024::parallel;
 use itertools::Itertools;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::Read;
 
 #[derive(Clone, Debug)]
 enum Operators {
@@ -24,12 +25,13 @@ impl Equation {
             .find_map(|ops| {
                 let mut r: i64 = self.numbers[0];
                 for (i, op) in ops.iter().enumerate() {
-                    match op {
-                        Operators::Add => r += self.numbers[i + 1],
-                        Operators::Multiply => r *= self.numbers[i + 1],
+                    // Overflowing combinations can never reach the result
+                    r = match op {
+                        Operators::Add => r.checked_add(self.numbers[i + 1])?,
+                        Operators::Multiply => r.checked_mul(self.numbers[i + 1])?,
                         Operators::Concatenate => {
                             let concat_str = format!("{}{}", r, self.numbers[i + 1]);
-                            r = concat_str.parse().unwrap_or(0);
+                            concat_str.parse().ok()?
                         }
                     }
                 }
@@ -44,36 +46,104 @@ impl Equation {
 }
 
 fn get_combination(n: usize) -> Vec<Vec<Operators>> {
-    std::iter::repeat([Operators::Add, Operators::Multiply, Operators::Concatenate])
-        .take(n)
-        .multi_cartesian_product()
-        .collect()
+    std::iter::repeat_n(
+        [Operators::Add, Operators::Multiply, Operators::Concatenate],
+        n,
+    )
+    .multi_cartesian_product()
+    .collect()
 }
 
-fn load_data(input_path: &str) -> Vec<Equation> {
-    let file = File::open(input_path).expect("Unable to open file");
-    let reader = BufReader::new(file);
-
-    reader
-        .lines()
-        .map_while(|line| line.ok())
+fn parse_data(s: &str) -> Result<Vec<Equation>, String> {
+    s.lines()
+        .filter(|line| !line.is_empty())
         .map(|line| {
-            let parts: Vec<&str> = line.split(": ").collect();
-            let result = parts[0].parse().expect("Invalid result");
-            let numbers: Vec<i64> = parts[1]
+            let (result, numbers) = line
+                .split_once(": ")
+                .ok_or_else(|| format!("missing ': ' in {:?}", line))?;
+            let result = result
+                .parse()
+                .map_err(|e| format!("Invalid result: {}", e))?;
+            let numbers: Vec<i64> = numbers
                 .split_whitespace()
-                .map(|n| n.parse().expect("Invalid number"))
-                .collect();
-            Equation { numbers, result }
+                .map(|n| n.parse().map_err(|e| format!("Invalid number: {}", e)))
+                .collect::<Result<_, _>>()?;
+            if numbers.is_empty() {
+                return Err(format!("no numbers in {:?}", line));
+            }
+            Ok(Equation { numbers, result })
         })
         .collect()
 }
 
+fn load_data(input_path: &str) -> Vec<Equation> {
+    let mut file = File::open(input_path).expect("Unable to open file");
+    let mut s = String::new();
+    file.read_to_string(&mut s).expect("Unable to read file");
+    parse_data(&s).unwrap_or_else(|e| panic!("{}", e))
+}
+
 fn main() {
+    parallel::configure();
     // let input = load_data("inputs/example_input07.txt");
     let input = load_data("inputs/input07.txt");
 
-    let result: i64 = input.iter().map(|eq| eq.solve()).sum();
+    let result: i64 = parallel::map(&input, |eq| eq.solve()).iter().sum();
 
     println!("sum total equations: {:?}", result);
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use proptest::prelude::*;
+
+    // Try every operator at every position, recursively
+    fn naive_solvable(target: i64, acc: i64, rest: &[i64]) -> bool {
+        match rest.split_first() {
+            None => acc == target,
+            Some((&n, rest)) => {
+                let concat = format!("{}{}", acc, n).parse().unwrap();
+                naive_solvable(target, acc + n, rest)
+                    || naive_solvable(target, acc * n, rest)
+                    || naive_solvable(target, concat, rest)
+            }
+        }
+    }
+
+    #[test]
+    fn test_example_data() {
+        let input = load_data("inputs/example_input07.txt");
+
+        assert_eq!(input.iter().map(|eq| eq.solve()).sum::<i64>(), 11387);
+        assert!(parse_data("190 10 19").is_err());
+        assert!(parse_data("190: ").is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn test_solve_matches_naive(
+            numbers in prop::collection::vec(1..30i64, 1..6),
+            result in 1..2000i64,
+        ) {
+            let solvable = naive_solvable(result, numbers[0], &numbers[1..]);
+            let equation = Equation { numbers, result };
+            prop_assert_eq!(equation.solve(), if solvable { result } else { 0 });
+        }
+
+        #[test]
+        fn test_solve_finds_built_result(
+            numbers in prop::collection::vec(1..30i64, 1..6),
+            ops in prop::collection::vec(0..3u8, 5),
+        ) {
+            let result = numbers[1..].iter().zip(&ops).fold(numbers[0], |r, (&n, op)| match op {
+                0 => r + n,
+                1 => r * n,
+                _ => format!("{}{}", r, n).parse().unwrap(),
+            });
+            let equation = Equation { numbers, result };
+            prop_assert_eq!(equation.solve(), result);
+        }
+    }
+}
@@ -1,7 +1,11 @@
-use aoc2024::read_input_to_file;
+use aoc2024::export::{Exporter, Frame, Palette};
+use aoc2024::geometry::{line_points, nth_multiple, Bounds, Point};
+use aoc2024::render::{Color, Overlay, Renderer};
+use aoc2024::{option, read_input_to_file};
 use std::{
     collections::{HashMap, HashSet},
     io::Read,
+    path::Path,
 };
 
 #[derive(Debug, PartialEq)]
@@ -27,38 +31,63 @@ impl Groups {
         Groups(m)
     }
 
-    fn check(self, map_coords: Vec<Coords>) -> HashSet<Coords> {
-        let mut anti = HashSet::new();
+    /// Every pair of antennas sharing a frequency, in both orders
+    fn pairs(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        self.0.values().flat_map(|antenna| pairs(antenna))
+    }
 
-        for (freq, antenna) in self.0.iter() {
-            // If there are less than 2 antennas with this frequency, skip
-            if antenna.len() < 2 {
-                continue;
-            }
+    /// Antinodes at twice the distance from one antenna to the other
+    fn antinodes(&self, bounds: &Bounds) -> HashSet<Coords> {
+        self.pairs()
+            .map(|(a, b)| nth_multiple(&a, &b, 2))
+            .filter(|p| bounds.contains(p))
+            .map(Coords::from)
+            .collect()
+    }
 
-            for a in antenna {
-                for b in antenna {
-                    // Skip if a and b are the same point
-                    if a == b {
-                        continue;
-                    }
+    /// Antinodes at every grid position in line with at least two antennas
+    fn check(&self, bounds: &Bounds) -> HashSet<Coords> {
+        self.check_by_frequency(bounds)
+            .into_values()
+            .flatten()
+            .collect()
+    }
 
-                    for c in map_coords.iter() {
-                        // Check if c is collinear with a and b
-                        if are_collinear(&a.coords, &b.coords, c) {
-                            anti.insert(Coords { x: c.x, y: c.y });
-                        }
-                    }
-                }
-            }
+    fn check_by_frequency(&self, bounds: &Bounds) -> HashMap<char, HashSet<Coords>> {
+        self.0
+            .iter()
+            .map(|(&freq, antenna)| {
+                let anti = pairs(antenna)
+                    .flat_map(|(a, b)| line_points(&a, &b, bounds))
+                    .map(Coords::from)
+                    .collect();
+                (freq, anti)
+            })
+            .collect()
+    }
+}
 
-            // Add all antennas with this frequency as antinodes
-            for a in antenna {
-                anti.insert(a.coords.clone());
-            }
-        }
+fn pairs(antenna: &[Antenna]) -> impl Iterator<Item = (Point, Point)> + '_ {
+    antenna.iter().flat_map(move |a| {
+        antenna
+            .iter()
+            .filter(move |b| a != *b)
+            .map(move |b| (a.coords.point(), b.coords.point()))
+    })
+}
 
-        anti
+impl Coords {
+    fn point(&self) -> Point {
+        Point::new(self.x as i64, self.y as i64)
+    }
+}
+
+impl From<Point> for Coords {
+    fn from(p: Point) -> Self {
+        Coords {
+            x: p.x as usize,
+            y: p.y as usize,
+        }
     }
 }
 
@@ -66,98 +95,101 @@ impl Groups {
 struct Map(Vec<Vec<char>>);
 
 impl Map {
-    fn coords(self) -> Vec<Coords> {
-        let mut c = vec![];
-
-        for (x, row) in self.0.iter().enumerate() {
-            for (y, &_cell) in row.iter().enumerate() {
-                c.push(Coords { x, y })
-            }
-        }
-        c
+    fn bounds(&self) -> Bounds {
+        // `x` is the row and `y` the column, as in `Coords`
+        let width = self.0.first().map_or(0, |row| row.len());
+        Bounds::new(self.0.len() as i64, width as i64)
     }
 
-    fn print(&self) {
-        for x in self.0.iter() {
-            // println!("{:?}", x.join("--").collect())
-            println!("{:?}", x.iter().collect::<String>());
-        }
+    /// The antinodes of each frequency in its own color
+    fn overlays(&self, antinodes: &HashMap<char, HashSet<Coords>>) -> Vec<Overlay> {
+        let mut freqs: Vec<&char> = antinodes.keys().collect();
+        freqs.sort();
+
+        freqs
+            .into_iter()
+            .zip(Color::CYCLE.iter().cycle())
+            .map(|(freq, &color)| {
+                let cells = antinodes[freq].iter().map(|c| {
+                    // Keep antennas visible, draw antinodes on empty cells
+                    let glyph = if self.0[c.x][c.y] == '.' {
+                        '#'
+                    } else {
+                        self.0[c.x][c.y]
+                    };
+                    ((c.x, c.y), glyph)
+                });
+                Overlay::new(format!("antinodes of {}", freq), color).glyphs(cells)
+            })
+            .collect()
     }
 
-    fn add_antinodes(&mut self, v: &HashSet<Coords>) {
-        for c in v {
-            if self.0[c.x][c.y] == '.' {
-                self.0[c.x][c.y] = '#';
-            }
-        }
+    fn print(&self, antinodes: &HashMap<char, HashSet<Coords>>) {
+        Renderer::new(&self.0)
+            .overlays(self.overlays(antinodes))
+            .print();
     }
-}
 
-fn distance(a: &Coords, b: &Coords) -> i32 {
-    ((b.x as i32 - a.x as i32).pow(2) + (b.y as i32 - a.y as i32).pow(2)).abs()
+    /// Save the map with its antinodes as an image, see `--export`
+    fn export(&self, antinodes: &HashMap<char, HashSet<Coords>>, path: &str) {
+        let frame = Frame::new(&self.0, &self.overlays(antinodes), &Palette::from_args());
+        Exporter::from_args()
+            .export(Path::new(path), &[frame])
+            .unwrap_or_else(|e| panic!("could not export {}: {}!", path, e));
+    }
 }
 
-fn are_collinear(a: &Coords, b: &Coords, c: &Coords) -> bool {
-    // First, check if points are collinear
-    (b.x as i32 - a.x as i32) * (c.y as i32 - a.y as i32)
-        == (c.x as i32 - a.x as i32) * (b.y as i32 - a.y as i32)
-
-    // if !collinear {
-    //     return false;
-    // }
-    // true
-
-    // // Calculate distances
-    // let dist_ab = ((b.x as i32 - a.x as i32).pow(2) + (b.y as i32 - a.y as i32).pow(2)).abs();
-    // let dist_ac = ((c.x as i32 - a.x as i32).pow(2) + (c.y as i32 - a.y as i32).pow(2)).abs();
-    //
-    // // Check if distances are equal
-    // dist_ab == dist_ac
+fn parse(s: &str) -> (Map, Vec<Antenna>) {
+    let mut antennas = vec![];
+    let map: Vec<Vec<char>> = s
+        .trim() // Remove leading/trailing whitespace
+        .split('\n')
+        .enumerate()
+        .map(|(x, l)| {
+            l.chars()
+                .enumerate()
+                .map(|(y, c)| {
+                    if c != '.' {
+                        antennas.push(Antenna {
+                            frequency: c,
+                            coords: Coords { x, y },
+                        })
+                    };
+                    c
+                })
+                .collect()
+        })
+        .collect();
+    // println!("{:?}", antennas);
+    (Map(map), antennas)
 }
 
 fn load(input_f: &str) -> (Map, Vec<Antenna>) {
     let mut file = read_input_to_file(input_f);
-    let mut antennas = vec![];
     let mut s = String::new();
     match file.read_to_string(&mut s) {
-        Ok(_) => {
-            let map: Vec<Vec<char>> = s
-                .trim() // Remove leading/trailing whitespace
-                .split('\n')
-                .enumerate()
-                .map(|(x, l)| {
-                    l.chars()
-                        .enumerate()
-                        .map(|(y, c)| {
-                            if c != '.' {
-                                antennas.push(Antenna {
-                                    frequency: c,
-                                    coords: Coords { x, y },
-                                })
-                            };
-                            c
-                        })
-                        .collect()
-                })
-                .collect();
-            // println!("{:?}", antennas);
-            (Map(map), antennas)
-        }
+        Ok(_) => parse(&s),
         Err(e) => panic!("{:?}", e),
     }
 }
 
 fn main() {
-    // let (mut map, antennas) = load("inputs/example_input08.txt");
-    let (mut map, antennas) = load("inputs/input08.txt");
+    // let (map, antennas) = load("inputs/example_input08.txt");
+    let (map, antennas) = load("inputs/input08.txt");
     //
 
     let groups = Groups::new(antennas);
+    let bounds = map.bounds();
+
+    println!("antinodes: {:?}", groups.antinodes(&bounds).len());
 
-    let antinodes = groups.check(map.clone().coords());
+    let antinodes = groups.check(&bounds);
+    let by_frequency = groups.check_by_frequency(&bounds);
 
-    map.add_antinodes(&antinodes);
-    map.print();
+    map.print(&by_frequency);
+    if let Some(path) = option("--export") {
+        map.export(&by_frequency, &path);
+    }
 
     println!("{:?}", antinodes.len())
 }
@@ -166,6 +198,71 @@ fn main() {
 mod tests {
 
     use super::*;
+    use proptest::prelude::*;
+
+    // Every cell in line with a pair of antennas sharing a frequency
+    fn naive_check(antennas: &[Antenna], rows: usize, cols: usize) -> HashSet<Coords> {
+        let collinear = |a: &Coords, b: &Coords, c: &Coords| {
+            (b.x as i32 - a.x as i32) * (c.y as i32 - a.y as i32)
+                == (c.x as i32 - a.x as i32) * (b.y as i32 - a.y as i32)
+        };
+        let mut anti = HashSet::new();
+        for a in antennas {
+            for b in antennas {
+                if a == b || a.frequency != b.frequency {
+                    continue;
+                }
+                for x in 0..rows {
+                    for y in 0..cols {
+                        let c = Coords { x, y };
+                        if collinear(&a.coords, &b.coords, &c) {
+                            anti.insert(c);
+                        }
+                    }
+                }
+            }
+        }
+        anti
+    }
+
+    // A map with a few antennas on distinct cells
+    fn antenna_map() -> impl Strategy<Value = (usize, usize, Vec<Antenna>)> {
+        (1..12usize, 1..12usize).prop_flat_map(|(rows, cols)| {
+            let cells: Vec<(usize, usize)> = (0..rows)
+                .flat_map(|x| (0..cols).map(move |y| (x, y)))
+                .collect();
+            let max = cells.len().min(8);
+            let antennas = prop::sample::subsequence(cells, 0..=max)
+                .prop_flat_map(|cells| {
+                    let n = cells.len();
+                    (
+                        Just(cells),
+                        prop::collection::vec(prop::sample::select(vec!['a', 'b', '0']), n),
+                    )
+                })
+                .prop_map(|(cells, freqs)| {
+                    cells
+                        .into_iter()
+                        .zip(freqs)
+                        .map(|((x, y), frequency)| Antenna {
+                            frequency,
+                            coords: Coords { x, y },
+                        })
+                        .collect::<Vec<_>>()
+                });
+            (Just(rows), Just(cols), antennas)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_check_matches_naive((rows, cols, antennas) in antenna_map()) {
+            let expected = naive_check(&antennas, rows, cols);
+            let groups = Groups::new(antennas);
+            let bounds = Bounds::new(rows as i64, cols as i64);
+            prop_assert_eq!(groups.check(&bounds), expected);
+        }
+    }
 
     #[test]
     fn test_exemple_data() {
@@ -173,9 +270,10 @@ mod tests {
         // let (map, antennas) = load("inputs/input08.txt");
 
         let groups = Groups::new(antennas);
+        let bounds = map.bounds();
 
-        let antinodes = groups.check(map.coords());
-        // assert_eq!(antinodes.len(), 14);
+        assert_eq!(groups.antinodes(&bounds).len(), 14);
+        let antinodes = groups.check(&bounds);
         assert_eq!(antinodes.len(), 34);
     }
 }
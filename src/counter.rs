@@ -0,0 +1,145 @@
+// Frequency counter
+//
+// - count how many times each value appears, in a single pass
+// - most common values, merging counters and dot products between them
+
+use std::{
+    collections::{hash_map, HashMap},
+    hash::Hash,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counter<T: Hash + Eq> {
+    counts: HashMap<T, usize>,
+}
+
+impl<T: Hash + Eq> Default for Counter<T> {
+    fn default() -> Self {
+        Counter {
+            counts: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Hash + Eq> Counter<T> {
+    pub fn new() -> Self {
+        Counter::default()
+    }
+
+    pub fn add(&mut self, item: T) {
+        self.add_n(item, 1);
+    }
+
+    pub fn add_n(&mut self, item: T, n: usize) {
+        *self.counts.entry(item).or_default() += n;
+    }
+
+    /// How many times `item` was counted, 0 if never seen
+    pub fn get(&self, item: &T) -> usize {
+        self.counts.get(item).copied().unwrap_or(0)
+    }
+
+    /// Number of distinct values
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Number of values counted, repetitions included
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    pub fn iter(&self) -> hash_map::Iter<'_, T, usize> {
+        self.counts.iter()
+    }
+
+    /// Add every count of `other` to this counter
+    pub fn merge(&mut self, other: Counter<T>) {
+        for (item, n) in other.counts {
+            self.add_n(item, n);
+        }
+    }
+
+    /// Sum of `count_a * count_b` over the values seen by both counters
+    pub fn dot(&self, other: &Counter<T>) -> usize {
+        self.pairs(other).map(|(_, a, b)| a * b).sum()
+    }
+
+    /// Sum of `weight(value) * count_a * count_b` over the values seen by both
+    pub fn dot_by(&self, other: &Counter<T>, weight: impl Fn(&T) -> i64) -> i64 {
+        self.pairs(other)
+            .map(|(item, a, b)| weight(item) * (a * b) as i64)
+            .sum()
+    }
+
+    /// Values seen by both counters, with both counts
+    fn pairs<'a>(&'a self, other: &'a Counter<T>) -> impl Iterator<Item = (&'a T, usize, usize)> {
+        // Walk the smaller counter, look up in the larger one
+        let (small, large, swapped) = if self.len() <= other.len() {
+            (self, other, false)
+        } else {
+            (other, self, true)
+        };
+        small.counts.iter().filter_map(move |(item, &a)| {
+            let b = *large.counts.get(item)?;
+            Some(if swapped { (item, b, a) } else { (item, a, b) })
+        })
+    }
+}
+
+impl<T: Hash + Eq + Ord> Counter<T> {
+    /// The `n` most common values, ties broken by the smallest value
+    pub fn most_common(&self, n: usize) -> Vec<(&T, usize)> {
+        let mut counts: Vec<(&T, usize)> = self.counts.iter().map(|(t, &c)| (t, c)).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts.truncate(n);
+        counts
+    }
+}
+
+impl<T: Hash + Eq> FromIterator<T> for Counter<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut counter = Counter::new();
+        counter.extend(iter);
+        counter
+    }
+}
+
+impl<T: Hash + Eq> Extend<T> for Counter<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|item| self.add(item));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_and_most_common() {
+        let mut c: Counter<char> = "abracadabra".chars().collect();
+        assert_eq!(c.get(&'a'), 5);
+        assert_eq!(c.get(&'z'), 0);
+        assert_eq!(c.len(), 5);
+        assert_eq!(c.total(), 11);
+        assert_eq!(c.most_common(3), vec![(&'a', 5), (&'b', 2), (&'r', 2)]);
+
+        c.merge("zz".chars().collect());
+        assert_eq!(c.most_common(3), vec![(&'a', 5), (&'b', 2), (&'r', 2)]);
+        assert_eq!(c.get(&'z'), 2);
+    }
+
+    #[test]
+    fn test_dot() {
+        let a: Counter<i64> = [3, 4, 2, 1, 3, 3].into_iter().collect();
+        let b: Counter<i64> = [4, 3, 5, 3, 9, 3].into_iter().collect();
+        assert_eq!(a.dot(&b), 10);
+        assert_eq!(b.dot(&a), 10);
+        // day01 example similarity score
+        assert_eq!(a.dot_by(&b, |x| *x), 31);
+    }
+}
@@ -0,0 +1,348 @@
+// Image export of grid states
+//
+// - every cell of the grid becomes a square of a single color
+// - base glyphs get their color from a configurable palette
+// - overlays (see `render`) are drawn on top with their own color
+// - PNG and SVG hold one frame, GIF holds an animation of many
+
+use crate::render::{Color, Overlay};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    /// Parse `rrggbb`, with or without a leading `#`
+    pub fn parse(s: &str) -> Option<Rgb> {
+        let s = s.strip_prefix('#').unwrap_or(s);
+        if s.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(s.get(i..i + 2)?, 16).ok();
+        Some(Rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+impl From<Color> for Rgb {
+    fn from(color: Color) -> Self {
+        match color {
+            Color::Red => Rgb(220, 50, 47),
+            Color::Green => Rgb(133, 153, 0),
+            Color::Yellow => Rgb(181, 137, 0),
+            Color::Blue => Rgb(38, 139, 210),
+            Color::Magenta => Rgb(211, 54, 130),
+            Color::Cyan => Rgb(42, 161, 152),
+            Color::White => Rgb(238, 232, 213),
+            Color::Grey => Rgb(88, 110, 117),
+            Color::Dim => Rgb(7, 54, 66),
+        }
+    }
+}
+
+/// Color of each glyph of the base grid
+#[derive(Debug, Clone)]
+pub struct Palette {
+    glyphs: HashMap<char, Rgb>,
+    default: Rgb,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        let glyphs = HashMap::from([('.', Rgb(0, 43, 54)), ('#', Rgb(147, 161, 161))]);
+        Palette {
+            glyphs,
+            default: Rgb(101, 123, 131),
+        }
+    }
+}
+
+impl Palette {
+    pub fn set(&mut self, glyph: char, color: Rgb) {
+        self.glyphs.insert(glyph, color);
+    }
+
+    pub fn get(&self, glyph: char) -> Rgb {
+        self.glyphs.get(&glyph).copied().unwrap_or(self.default)
+    }
+
+    /// Default palette with the overrides given by `--palette SPEC`
+    pub fn from_args() -> Self {
+        let mut palette = Palette::default();
+        if let Some(spec) = crate::option("--palette") {
+            palette
+                .apply_spec(&spec)
+                .unwrap_or_else(|e| panic!("{}", e));
+        }
+        palette
+    }
+
+    /// Override colors from a spec like `#=ff0000,.=000000`
+    pub fn apply_spec(&mut self, spec: &str) -> Result<(), String> {
+        for entry in spec.split(',').filter(|e| !e.is_empty()) {
+            let mut chars = entry.chars();
+            let (Some(glyph), Some('=')) = (chars.next(), chars.next()) else {
+                return Err(format!("invalid palette entry {:?}", entry));
+            };
+            let color = Rgb::parse(chars.as_str())
+                .ok_or_else(|| format!("invalid color in palette entry {:?}", entry))?;
+            self.set(glyph, color);
+        }
+        Ok(())
+    }
+}
+
+/// One color per grid cell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<Rgb>,
+}
+
+impl Frame {
+    pub fn new(grid: &[Vec<char>], overlays: &[Overlay], palette: &Palette) -> Self {
+        let height = grid.len();
+        let width = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut cells = vec![palette.get(' '); width * height];
+        for (row, glyphs) in grid.iter().enumerate() {
+            for (col, &glyph) in glyphs.iter().enumerate() {
+                cells[row * width + col] = palette.get(glyph);
+            }
+        }
+        for overlay in overlays {
+            for mark in &overlay.marks {
+                if mark.row < height && mark.col < width {
+                    cells[mark.row * width + mark.col] = overlay.color.into();
+                }
+            }
+        }
+        Frame {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Pixels as RGB bytes, each cell drawn as a `scale` x `scale` square
+    fn pixels(&self, scale: usize) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.width * self.height * scale * scale * 3);
+        for row in self.cells.chunks(self.width.max(1)) {
+            for _ in 0..scale {
+                for cell in row {
+                    for _ in 0..scale {
+                        bytes.extend([cell.0, cell.1, cell.2]);
+                    }
+                }
+            }
+        }
+        bytes
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Png,
+    Svg,
+    Gif,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "png" => Some(Format::Png),
+            "svg" => Some(Format::Svg),
+            "gif" => Some(Format::Gif),
+            _ => None,
+        }
+    }
+}
+
+pub struct Exporter {
+    /// Size of a grid cell in pixels
+    pub scale: usize,
+    /// Time between animation frames, in hundredths of a second
+    pub delay: u16,
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Exporter { scale: 4, delay: 5 }
+    }
+}
+
+impl Exporter {
+    /// Exporter configured by `--cell-size N` and `--delay N`
+    pub fn from_args() -> Self {
+        let mut exporter = Exporter::default();
+        if let Some(scale) = crate::option("--cell-size") {
+            exporter.scale = scale.parse().expect("invalid --cell-size");
+        }
+        if let Some(delay) = crate::option("--delay") {
+            exporter.delay = delay.parse().expect("invalid --delay");
+        }
+        exporter
+    }
+
+    /// Write `frames` to `path`, the format is picked from the extension
+    ///
+    /// PNG and SVG only hold a single image, so only the last frame is kept.
+    pub fn export(&self, path: &Path, frames: &[Frame]) -> io::Result<()> {
+        let format = Format::from_path(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown image format for {}", path.display()),
+            )
+        })?;
+        let Some(last) = frames.last() else {
+            return Err(nothing_to_export());
+        };
+        let mut out = BufWriter::new(File::create(path)?);
+        match format {
+            Format::Png => self.write_png(&mut out, last)?,
+            Format::Svg => self.write_svg(&mut out, last)?,
+            Format::Gif => self.write_gif(&mut out, frames)?,
+        }
+        out.flush()
+    }
+
+    pub fn write_png(&self, out: impl Write, frame: &Frame) -> io::Result<()> {
+        let (w, h) = (frame.width * self.scale, frame.height * self.scale);
+        let mut encoder = png::Encoder::new(out, w as u32, h as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer
+            .write_image_data(&frame.pixels(self.scale))
+            .map_err(io::Error::other)
+    }
+
+    pub fn write_svg(&self, mut out: impl Write, frame: &Frame) -> io::Result<()> {
+        let s = self.scale;
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" shape-rendering="crispEdges">"#,
+            frame.width * s,
+            frame.height * s
+        )?;
+        for (i, cell) in frame.cells.iter().enumerate() {
+            let (row, col) = (i / frame.width, i % frame.width);
+            writeln!(
+                out,
+                r#"<rect x="{}" y="{}" width="{s}" height="{s}" fill="{}"/>"#,
+                col * s,
+                row * s,
+                cell.hex()
+            )?;
+        }
+        writeln!(out, "</svg>")
+    }
+
+    pub fn write_gif(&self, out: impl Write, frames: &[Frame]) -> io::Result<()> {
+        let Some(first) = frames.first() else {
+            return Err(nothing_to_export());
+        };
+        // Grids only use a handful of colors, so they all fit in one palette
+        let mut colors: Vec<Rgb> = vec![];
+        for frame in frames {
+            for cell in &frame.cells {
+                if !colors.contains(cell) {
+                    colors.push(*cell);
+                }
+            }
+        }
+        if colors.len() > 256 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a GIF can use at most 256 colors",
+            ));
+        }
+        let palette: Vec<u8> = colors.iter().flat_map(|c| [c.0, c.1, c.2]).collect();
+
+        let too_big = || io::Error::new(io::ErrorKind::InvalidInput, "grid too big for a GIF");
+        let (w, h) = (first.width * self.scale, first.height * self.scale);
+        let (w, h) = (
+            u16::try_from(w).map_err(|_| too_big())?,
+            u16::try_from(h).map_err(|_| too_big())?,
+        );
+
+        let mut encoder = gif::Encoder::new(out, w, h, &palette).map_err(io::Error::other)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(io::Error::other)?;
+        for frame in frames {
+            let indices: Vec<u8> = frame
+                .pixels(self.scale)
+                .chunks(3)
+                .map(|p| {
+                    let rgb = Rgb(p[0], p[1], p[2]);
+                    colors.iter().position(|c| *c == rgb).unwrap_or(0) as u8
+                })
+                .collect();
+            let gif_frame = gif::Frame {
+                width: w,
+                height: h,
+                delay: self.delay,
+                buffer: indices.into(),
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&gif_frame).map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+}
+
+fn nothing_to_export() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "nothing to export")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_spec() {
+        let mut palette = Palette::default();
+        palette.apply_spec("#=ff0000,x=#00ff00").unwrap();
+        assert_eq!(palette.get('#'), Rgb(255, 0, 0));
+        assert_eq!(palette.get('x'), Rgb(0, 255, 0));
+        assert!(palette.apply_spec("#ff0000").is_err());
+        assert!(palette.apply_spec("#=red").is_err());
+    }
+
+    #[test]
+    fn test_frame_and_svg() {
+        let grid = vec![vec!['.', '#']];
+        let overlay = Overlay::new("mark", Color::Red).cells([(0, 0)]);
+        let frame = Frame::new(&grid, &[overlay], &Palette::default());
+        assert_eq!(frame.cells, vec![Rgb::from(Color::Red), Rgb(147, 161, 161)]);
+
+        let exporter = Exporter { scale: 2, delay: 1 };
+        assert_eq!(frame.pixels(2).len(), 2 * 2 * 2 * 3);
+        let mut svg = vec![];
+        exporter.write_svg(&mut svg, &frame).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.contains(r##"<rect x="2" y="0" width="2" height="2" fill="#93a1a1"/>"##));
+
+        let mut png = vec![];
+        exporter.write_png(&mut png, &frame).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        let mut gif = vec![];
+        exporter
+            .write_gif(&mut gif, &[frame.clone(), frame])
+            .unwrap();
+        assert!(gif.starts_with(b"GIF89a"));
+
+        let empty = exporter.write_gif(&mut vec![], &[]).unwrap_err();
+        assert_eq!(empty.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(empty.to_string(), "nothing to export");
+    }
+}
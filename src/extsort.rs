@@ -0,0 +1,258 @@
+// External merge sort of numbers that do not fit in memory
+//
+// - values are buffered until a run is full, then sorted and spilled to a
+//   temporary file
+// - the runs are merged back lazily, with one small read buffer per run
+// - several sorters can be merged together, each value is tagged with the
+//   sorter it came from
+// - at most `FAN_IN` runs are merged at once: past that, `finish` merges
+//   groups of runs into longer ones, in as many passes as needed
+// - memory stays at one run plus `FAN_IN` read buffers per sorter, whatever
+//   the input size
+// - run files are removed when the `Runs` are dropped
+
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Tells run files apart, when several sorters share the temp directory
+static NEXT_RUN: AtomicUsize = AtomicUsize::new(0);
+
+/// Most runs read at the same time, by a sorter
+pub const FAN_IN: usize = 64;
+
+pub struct Sorter {
+    run_len: usize,
+    buffer: Vec<i32>,
+    runs: Runs,
+}
+
+impl Sorter {
+    /// Spill a sorted run to disk every `run_len` values
+    pub fn new(run_len: usize) -> Self {
+        Sorter {
+            run_len: run_len.max(1),
+            buffer: vec![],
+            runs: Runs { paths: vec![] },
+        }
+    }
+
+    pub fn push(&mut self, value: i32) -> io::Result<()> {
+        self.buffer.push(value);
+        if self.buffer.len() >= self.run_len {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Spill what is left, the values can then be merged back from at most
+    /// `FAN_IN` runs
+    pub fn finish(mut self) -> io::Result<Runs> {
+        if !self.buffer.is_empty() {
+            self.spill()?;
+        }
+        while self.runs.len() > FAN_IN {
+            let mut merged = Runs { paths: vec![] };
+            while !self.runs.is_empty() {
+                let n = self.runs.len().min(FAN_IN);
+                let mut group = Runs {
+                    paths: self.runs.paths.drain(..n).collect(),
+                };
+                if n == 1 {
+                    merged.paths.append(&mut group.paths);
+                    continue;
+                }
+                let mut out = merged.create()?;
+                for item in merge(&[&group])? {
+                    out.write_all(&item?.0.to_le_bytes())?;
+                }
+                out.flush()?;
+                // Dropping the group removes its files
+            }
+            self.runs = merged;
+        }
+        Ok(self.runs)
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.buffer.sort_unstable();
+        let mut out = self.runs.create()?;
+        for value in self.buffer.drain(..) {
+            out.write_all(&value.to_le_bytes())?;
+        }
+        out.flush()
+    }
+}
+
+/// Sorted runs on disk
+pub struct Runs {
+    paths: Vec<PathBuf>,
+}
+
+impl Runs {
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// A new empty run file, removed with the others
+    ///
+    /// Never opens a file that already exists, so nothing else in the temp
+    /// directory gets overwritten or removed.
+    fn create(&mut self) -> io::Result<BufWriter<File>> {
+        loop {
+            let path = std::env::temp_dir().join(format!(
+                "aoc2024-{}-{}.run",
+                std::process::id(),
+                NEXT_RUN.fetch_add(1, Ordering::Relaxed)
+            ));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => {
+                    self.paths.push(path);
+                    return Ok(BufWriter::new(file));
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for Runs {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Every value of `runs` in ascending order, with the index of its `Runs`
+///
+/// Can be called more than once, each merge reads the files from the start.
+pub fn merge(runs: &[&Runs]) -> io::Result<Merge> {
+    let mut merge = Merge {
+        readers: vec![],
+        heap: BinaryHeap::new(),
+    };
+    for (source, runs) in runs.iter().enumerate() {
+        for path in &runs.paths {
+            let mut reader = BufReader::with_capacity(8 * 1024, File::open(path)?);
+            if let Some(value) = read_value(&mut reader)? {
+                merge.heap.push(Reverse((value, merge.readers.len())));
+            }
+            merge.readers.push((source, reader));
+        }
+    }
+    Ok(merge)
+}
+
+/// K-way merge of the runs, smallest value first
+pub struct Merge {
+    readers: Vec<(usize, BufReader<File>)>,
+    /// Next value of each run that is not used up, with the run it came from
+    heap: BinaryHeap<Reverse<(i32, usize)>>,
+}
+
+impl Iterator for Merge {
+    type Item = io::Result<(i32, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((value, run)) = self.heap.pop()?;
+        let (source, reader) = &mut self.readers[run];
+        let source = *source;
+        match read_value(reader) {
+            Ok(Some(next)) => self.heap.push(Reverse((next, run))),
+            Ok(None) => {}
+            Err(e) => return Some(Err(e)),
+        }
+        Some(Ok((value, source)))
+    }
+}
+
+fn read_value(reader: &mut impl Read) -> io::Result<Option<i32>> {
+    let mut bytes = [0; 4];
+    match reader.read_exact(&mut bytes) {
+        Ok(()) => Ok(Some(i32::from_le_bytes(bytes))),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sorts_across_runs() {
+        let values: Vec<i32> = (0..1000).map(|i| (i * 7919) % 1013 - 500).collect();
+        let mut sorter = Sorter::new(64);
+        for &v in &values {
+            sorter.push(v).unwrap();
+        }
+        let runs = sorter.finish().unwrap();
+        assert_eq!(runs.len(), 16);
+
+        let mut expected = values.clone();
+        expected.sort();
+        let merged: Vec<i32> = merge(&[&runs]).unwrap().map(|v| v.unwrap().0).collect();
+        assert_eq!(merged, expected);
+        // A second merge starts over
+        assert_eq!(merge(&[&runs]).unwrap().count(), 1000);
+
+        let mut other = Sorter::new(2);
+        for v in [5, -600, 5] {
+            other.push(v).unwrap();
+        }
+        let other = other.finish().unwrap();
+        let tagged: Vec<(i32, usize)> = merge(&[&runs, &other])
+            .unwrap()
+            .map(Result::unwrap)
+            .filter(|&(v, _)| v == -600 || v == 5 || v == -500)
+            .collect();
+        assert_eq!(tagged, [(-600, 1), (-500, 0), (5, 0), (5, 1), (5, 1)]);
+
+        let paths = runs.paths.clone();
+        drop(runs);
+        assert!(paths.iter().all(|p| !p.exists()));
+    }
+
+    #[test]
+    fn test_fan_in() {
+        let values: Vec<i32> = (0..10_000).map(|i| (i * 7919) % 10007).collect();
+        let mut sorter = Sorter::new(2);
+        for &v in &values {
+            sorter.push(v).unwrap();
+        }
+        let runs = sorter.finish().unwrap();
+        assert!(runs.len() <= FAN_IN, "{} runs", runs.len());
+
+        let mut expected = values.clone();
+        expected.sort();
+        let merged: Vec<i32> = merge(&[&runs]).unwrap().map(|v| v.unwrap().0).collect();
+        assert_eq!(merged, expected);
+    }
+
+    #[test]
+    fn test_never_overwrites() {
+        // The name the next run would get
+        let next = NEXT_RUN.load(Ordering::Relaxed);
+        let taken =
+            std::env::temp_dir().join(format!("aoc2024-{}-{}.run", std::process::id(), next));
+        fs::write(&taken, "keep").unwrap();
+
+        let mut sorter = Sorter::new(1);
+        sorter.push(1).unwrap();
+        let runs = sorter.finish().unwrap();
+        drop(runs);
+        assert_eq!(fs::read_to_string(&taken).unwrap(), "keep");
+        fs::remove_file(&taken).unwrap();
+    }
+}
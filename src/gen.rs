@@ -0,0 +1,331 @@
+// Seeded random puzzle inputs
+//
+// - one generator per day, producing that day's input format
+// - a small SplitMix64 generator keeps the output identical for a given seed
+// - `size` scales the input: lines, reports, grid side, ... (see each day)
+
+use crate::lab::Lab;
+use crate::walker::{Outcome, Walker};
+
+/// SplitMix64, deterministic and good enough for test inputs
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `lo..=hi`
+    pub fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        lo + (self.next_u64() % (hi - lo + 1) as u64) as i64
+    }
+
+    /// True `percent` % of the time
+    pub fn chance(&mut self, percent: u32) -> bool {
+        self.next_u64() % 100 < percent as u64
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.range(0, items.len() as i64 - 1) as usize]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.range(0, i as i64) as usize);
+        }
+    }
+}
+
+pub struct Options {
+    pub seed: u64,
+    pub size: usize,
+    /// Share of day07 equations that can be solved, in percent
+    pub solvable: u32,
+}
+
+/// Input for `day`, `None` if there is no generator for it
+pub fn generate(day: u32, options: &Options) -> Option<String> {
+    let mut rng = Rng::new(options.seed);
+    let size = options.size;
+    let input = match day {
+        1 => day01(&mut rng, size),
+        2 => day02(&mut rng, size),
+        3 => day03(&mut rng, size),
+        4 => day04(&mut rng, size),
+        5 => day05(&mut rng, size),
+        6 => day06(&mut rng, size),
+        7 => day07(&mut rng, size, options.solvable),
+        8 => day08(&mut rng, size),
+        _ => return None,
+    };
+    Some(input)
+}
+
+/// `size` lines of two location ids, drawn from a small pool so they repeat
+fn day01(rng: &mut Rng, size: usize) -> String {
+    let pool: Vec<i64> = (0..size.max(1)).map(|_| rng.range(10000, 99999)).collect();
+    (0..size)
+        .map(|_| format!("{}   {}\n", rng.pick(&pool), rng.pick(&pool)))
+        .collect()
+}
+
+/// `size` reports, mostly safe ones with a few bad levels thrown in
+fn day02(rng: &mut Rng, size: usize) -> String {
+    let mut out = String::new();
+    for _ in 0..size {
+        let len = rng.range(5, 8);
+        let direction = if rng.chance(50) { 1 } else { -1 };
+        let mut level = rng.range(10, 90);
+        let mut levels = vec![];
+        for _ in 0..len {
+            levels.push(level);
+            level += direction * rng.range(1, 3);
+        }
+        // Break some of them: flat steps, big jumps or direction changes
+        while rng.chance(40) {
+            let i = rng.range(0, len - 1) as usize;
+            levels[i] += rng.range(-5, 5);
+        }
+        let levels: Vec<String> = levels.iter().map(|l| l.to_string()).collect();
+        out += &levels.join(" ");
+        out.push('\n');
+    }
+    out
+}
+
+/// `size` instructions hidden in noise, with decoys that must not match
+fn day03(rng: &mut Rng, size: usize) -> String {
+    const NOISE: &[&str] = &[
+        "!", "@", "#", "$", "%", "^", "&", "*", "(", ")", "[", "]", "{", "}", "<", ">", "'", ",",
+        " ", "?", "from()", "what()", "select()", "why()", "who()", "how()", "where()",
+    ];
+    let mut out = String::new();
+    for i in 0..size {
+        let (a, b) = (rng.range(1, 999), rng.range(1, 999));
+        let instruction = match rng.range(0, 9) {
+            0 => "do()".to_string(),
+            1 => "don't()".to_string(),
+            // Decoys
+            2 => format!("mul({}*", a),
+            3 => format!("mul ( {} , {} )", a, b),
+            4 => format!("mul[{},{}]", a, b),
+            5 => format!("mul({},{}!", a, b),
+            6 => format!("mul({},{})", rng.range(1000, 9999), b),
+            _ => format!("mul({},{})", a, b),
+        };
+        for _ in 0..rng.range(0, 6) {
+            out += *rng.pick(NOISE);
+        }
+        out += &instruction;
+        if i % 100 == 99 {
+            out.push('\n');
+        }
+    }
+    out.push('\n');
+    out
+}
+
+/// `size` x `size` letters, only from `XMAS` so words show up often
+fn day04(rng: &mut Rng, size: usize) -> String {
+    let letters = ['X', 'M', 'A', 'S'];
+    let mut out = String::new();
+    for _ in 0..size {
+        out.extend((0..size).map(|_| *rng.pick(&letters)));
+        out.push('\n');
+    }
+    out
+}
+
+/// `size` updates over pages that follow one hidden total order
+///
+/// Every pair of pages has a rule, so each update has exactly one correct
+/// ordering. About half the updates are printed in that order.
+fn day05(rng: &mut Rng, size: usize) -> String {
+    let mut pages: Vec<i64> = (10..100).collect();
+    rng.shuffle(&mut pages);
+    pages.truncate(size.clamp(5, 90));
+
+    let mut out = String::new();
+    for (i, a) in pages.iter().enumerate() {
+        for b in &pages[i + 1..] {
+            out += &format!("{}|{}\n", a, b);
+        }
+    }
+    out.push('\n');
+
+    // Updates have an odd number of pages, so there is a middle one
+    let longest = (pages.len() - 1) | 1;
+    for _ in 0..size {
+        let len = ((rng.range(2, 11) * 2 + 1) as usize).min(longest);
+        let mut positions: Vec<usize> = (0..pages.len()).collect();
+        rng.shuffle(&mut positions);
+        let mut update: Vec<usize> = positions[..len].to_vec();
+        if rng.chance(50) {
+            update.sort();
+        }
+        let update: Vec<String> = update.iter().map(|&i| pages[i].to_string()).collect();
+        out += &update.join(",");
+        out.push('\n');
+    }
+    out
+}
+
+/// `size` x `size` lab with scattered obstacles and exactly one guard, who
+/// eventually walks out
+fn day06(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(1);
+    let grid = loop {
+        let mut grid: Vec<Vec<char>> = (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| if rng.chance(8) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        let (x, y) = (rng.range(0, size as i64 - 1), rng.range(0, size as i64 - 1));
+        grid[y as usize][x as usize] = '^';
+        if guard_leaves(&grid) {
+            break grid;
+        }
+    };
+    grid.iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+/// Whether the guard of the day06 `grid` leaves it, rather than looping
+fn guard_leaves(grid: &[Vec<char>]) -> bool {
+    let lab = Lab(grid);
+    let Some(guard) = lab.find_guard() else {
+        return false;
+    };
+    matches!(Walker::new(&lab, guard).run(), Outcome::Exit { .. })
+}
+
+/// `size` equations, `solvable` % of them can be made true
+fn day07(rng: &mut Rng, size: usize, solvable: u32) -> String {
+    let mut out = String::new();
+    for _ in 0..size {
+        let numbers: Vec<i64> = (0..rng.range(2, 8)).map(|_| rng.range(1, 99)).collect();
+        let mut result = numbers[1..]
+            .iter()
+            .fold(numbers[0], |r, &n| match rng.range(0, 2) {
+                0 => r + n,
+                1 => r * n,
+                _ => concat(r, n),
+            });
+        if !rng.chance(solvable) {
+            // Nudge the result until nothing reaches it
+            while can_solve(result, numbers[0], &numbers[1..]) {
+                result += rng.range(1, 9);
+            }
+        }
+        let numbers: Vec<String> = numbers.iter().map(|n| n.to_string()).collect();
+        out += &format!("{}: {}\n", result, numbers.join(" "));
+    }
+    out
+}
+
+fn concat(a: i64, b: i64) -> i64 {
+    a * 10_i64.pow(b.to_string().len() as u32) + b
+}
+
+fn can_solve(target: i64, acc: i64, rest: &[i64]) -> bool {
+    match rest.split_first() {
+        None => acc == target,
+        Some((&n, rest)) => {
+            can_solve(target, acc + n, rest)
+                || can_solve(target, acc * n, rest)
+                || can_solve(target, concat(acc, n), rest)
+        }
+    }
+}
+
+/// `size` x `size` map with a few antennas per frequency
+fn day08(rng: &mut Rng, size: usize) -> String {
+    let size = size.max(1);
+    let frequencies: Vec<char> = ('0'..='9').chain('a'..='z').chain('A'..='Z').collect();
+    let mut grid = vec![vec!['.'; size]; size];
+    for &freq in &frequencies[..(size / 4).clamp(1, frequencies.len())] {
+        for _ in 0..rng.range(2, 4) {
+            let (x, y) = (rng.range(0, size as i64 - 1), rng.range(0, size as i64 - 1));
+            grid[y as usize][x as usize] = freq;
+        }
+    }
+    grid.iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(seed: u64, size: usize) -> Options {
+        Options {
+            seed,
+            size,
+            solvable: 50,
+        }
+    }
+
+    #[test]
+    fn test_deterministic() {
+        for day in 1..=8 {
+            let a = generate(day, &options(42, 20));
+            assert_eq!(a, generate(day, &options(42, 20)));
+            assert_ne!(a, generate(day, &options(43, 20)));
+        }
+        assert_eq!(generate(26, &options(42, 20)), None);
+    }
+
+    #[test]
+    fn test_one_guard() {
+        for seed in 0..20 {
+            let map = generate(6, &options(seed, 12)).unwrap();
+            assert_eq!(map.matches('^').count(), 1);
+            assert_eq!(map.lines().count(), 12);
+        }
+        // Looping maps are rolled again
+        for seed in 0..200 {
+            let map = generate(6, &options(seed, 30)).unwrap();
+            let grid: Vec<Vec<char>> = map.lines().map(|l| l.chars().collect()).collect();
+            assert!(guard_leaves(&grid), "seed {}", seed);
+        }
+        let looping = [".#..", "...#", "#^..", "..#."];
+        let grid: Vec<Vec<char>> = looping.iter().map(|l| l.chars().collect()).collect();
+        assert!(!guard_leaves(&grid));
+    }
+
+    #[test]
+    fn test_solvability() {
+        let input = generate(7, &options(7, 50)).unwrap();
+        let solvable = input
+            .lines()
+            .filter(|l| {
+                let (result, numbers) = l.split_once(": ").unwrap();
+                let numbers: Vec<i64> = numbers.split(' ').map(|n| n.parse().unwrap()).collect();
+                can_solve(result.parse().unwrap(), numbers[0], &numbers[1..])
+            })
+            .count();
+        assert!((15..=35).contains(&solvable), "{} solvable", solvable);
+        let all = Options {
+            solvable: 100,
+            ..options(7, 50)
+        };
+        let input = generate(7, &all).unwrap();
+        assert!(input.lines().all(|l| {
+            let (result, numbers) = l.split_once(": ").unwrap();
+            let numbers: Vec<i64> = numbers.split(' ').map(|n| n.parse().unwrap()).collect();
+            can_solve(result.parse().unwrap(), numbers[0], &numbers[1..])
+        }));
+    }
+}
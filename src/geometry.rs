@@ -0,0 +1,169 @@
+// Integer lattice geometry
+//
+// - lines and rays through two points, stepping by the gcd-reduced delta
+// - every point is clipped to a bounding box
+// - "k-th multiple" points along the offset between two points
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Point {
+    pub fn new(x: i64, y: i64) -> Self {
+        Point { x, y }
+    }
+
+    /// Offset needed to go from `self` to `other`
+    pub fn delta(&self, other: &Point) -> (i64, i64) {
+        (other.x - self.x, other.y - self.y)
+    }
+
+    pub fn offset(&self, dx: i64, dy: i64) -> Point {
+        Point::new(self.x + dx, self.y + dy)
+    }
+}
+
+/// Half-open bounding box: `x` in `min_x..max_x`, `y` in `min_y..max_y`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min_x: i64,
+    pub min_y: i64,
+    pub max_x: i64,
+    pub max_y: i64,
+}
+
+impl Bounds {
+    /// Bounds from the origin, `x` below `max_x` and `y` below `max_y`
+    pub fn new(max_x: i64, max_y: i64) -> Self {
+        Bounds {
+            min_x: 0,
+            min_y: 0,
+            max_x,
+            max_y,
+        }
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        p.x >= self.min_x && p.x < self.max_x && p.y >= self.min_y && p.y < self.max_y
+    }
+}
+
+pub fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Smallest lattice step along the direction from `a` to `b`
+///
+/// Returns `None` when both points are the same, since there is no direction.
+pub fn step(a: &Point, b: &Point) -> Option<(i64, i64)> {
+    let (dx, dy) = a.delta(b);
+    match gcd(dx, dy) {
+        0 => None,
+        g => Some((dx / g, dy / g)),
+    }
+}
+
+/// Point at `a + k * (b - a)`
+///
+/// `k = 0` is `a`, `k = 1` is `b`, `k = 2` is the reflection of `a` over `b`
+/// and `k = -1` the reflection of `b` over `a`.
+pub fn nth_multiple(a: &Point, b: &Point, k: i64) -> Point {
+    let (dx, dy) = a.delta(b);
+    a.offset(k * dx, k * dy)
+}
+
+/// Walk from `start` in steps of `(dx, dy)` while inside `bounds`
+fn walk(start: Point, (dx, dy): (i64, i64), bounds: &Bounds) -> impl Iterator<Item = Point> + '_ {
+    std::iter::successors(Some(start), move |p| Some(p.offset(dx, dy)))
+        .take_while(move |p| bounds.contains(p))
+}
+
+/// Every lattice point on the ray starting at `a` and passing through `b`
+pub fn ray_points(a: &Point, b: &Point, bounds: &Bounds) -> Vec<Point> {
+    match step(a, b) {
+        Some(s) => walk(*a, s, bounds).collect(),
+        None if bounds.contains(a) => vec![*a],
+        None => vec![],
+    }
+}
+
+/// Every lattice point on the infinite line through `a` and `b`
+///
+/// Points are ordered from the `a` side towards the `b` side.
+pub fn line_points(a: &Point, b: &Point, bounds: &Bounds) -> Vec<Point> {
+    let Some((dx, dy)) = step(a, b) else {
+        return ray_points(a, b, bounds);
+    };
+    // Range of k for which `a + k * step` stays inside the box
+    let (lo_x, hi_x) = steps_within(a.x, dx, bounds.min_x, bounds.max_x);
+    let (lo_y, hi_y) = steps_within(a.y, dy, bounds.min_y, bounds.max_y);
+    (lo_x.max(lo_y)..=hi_x.min(hi_y))
+        .map(|k| a.offset(k * dx, k * dy))
+        .collect()
+}
+
+/// Range of `k` such that `min <= start + k * d < max`
+fn steps_within(start: i64, d: i64, min: i64, max: i64) -> (i64, i64) {
+    match d {
+        0 if start >= min && start < max => (i64::MIN, i64::MAX),
+        0 => (1, 0),
+        // Mirror the axis so the step is always positive
+        d if d < 0 => steps_within(-start, -d, 1 - max, 1 - min),
+        d => (
+            -(start - min).div_euclid(d),
+            (max - 1 - start).div_euclid(d),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_reduces_step() {
+        let bounds = Bounds::new(10, 10);
+        let points = line_points(&Point::new(2, 2), &Point::new(4, 6), &bounds);
+        assert_eq!(
+            points,
+            vec![
+                Point::new(1, 0),
+                Point::new(2, 2),
+                Point::new(3, 4),
+                Point::new(4, 6),
+                Point::new(5, 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ray_and_multiples() {
+        let bounds = Bounds::new(5, 5);
+        let (a, b) = (Point::new(0, 0), Point::new(1, 1));
+        assert_eq!(ray_points(&a, &b, &bounds).len(), 5);
+        assert_eq!(ray_points(&b, &a, &bounds).len(), 2);
+        assert_eq!(nth_multiple(&a, &b, 3), Point::new(3, 3));
+        assert_eq!(nth_multiple(&a, &b, -1), Point::new(-1, -1));
+    }
+
+    #[test]
+    fn test_line_starting_outside_bounds() {
+        let bounds = Bounds::new(3, 3);
+        let points = line_points(&Point::new(-20, 0), &Point::new(-19, 0), &bounds);
+        assert_eq!(
+            points,
+            vec![Point::new(0, 0), Point::new(1, 0), Point::new(2, 0)]
+        );
+        let points = line_points(&Point::new(7, 7), &Point::new(6, 6), &bounds);
+        assert_eq!(
+            points,
+            vec![Point::new(2, 2), Point::new(1, 1), Point::new(0, 0)]
+        );
+    }
+}
@@ -0,0 +1,110 @@
+// The guard's lab from day 6
+//
+// - a grid of free cells `.` and obstacles `#`, the guard starts on `^`
+// - the guard walks straight ahead and turns right in front of an obstacle
+// - the patrol ends when the guard walks off the grid
+
+use crate::geometry::Point;
+use crate::walker::{Agent, StepRule};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    pub fn turn_right(&self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    pub fn glyph(&self) -> char {
+        match self {
+            Direction::Up => '^',
+            Direction::Right => '>',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+        }
+    }
+
+    pub fn offset(&self) -> (i64, i64) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Right => (1, 0),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+        }
+    }
+}
+
+pub type Guard = Agent<Direction>;
+
+/// The lab's rows, they do not need to have the same length
+pub struct Lab<'a>(pub &'a [Vec<char>]);
+
+impl Lab<'_> {
+    /// The guard at its starting position, heading up
+    pub fn find_guard(&self) -> Option<Guard> {
+        self.0.iter().enumerate().find_map(|(y, row)| {
+            let x = row.iter().position(|&c| c == '^')?;
+            Some(Guard::new(Point::new(x as i64, y as i64), Direction::Up))
+        })
+    }
+
+    /// Cell at `p`, `None` off the grid
+    pub fn get(&self, p: Point) -> Option<char> {
+        let row = self.0.get(usize::try_from(p.y).ok()?)?;
+        row.get(usize::try_from(p.x).ok()?).copied()
+    }
+}
+
+impl StepRule<Direction> for Lab<'_> {
+    fn step(&self, guard: &Guard) -> Option<Guard> {
+        let (dx, dy) = guard.heading.offset();
+        let next = guard.position.offset(dx, dy);
+
+        // if the guard fell of the map terminate the patrol
+        if self.get(next)? == '#' {
+            Some(Guard::new(guard.position, guard.heading.turn_right()))
+        } else {
+            Some(Guard::new(next, guard.heading))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::walker::{Outcome, Walker};
+
+    #[test]
+    fn test_patrol() {
+        let grid: Vec<Vec<char>> = [".#..", "....", ".^..", "...."]
+            .iter()
+            .map(|l| l.chars().collect())
+            .collect();
+        let lab = Lab(&grid);
+        let guard = lab.find_guard().unwrap();
+        assert_eq!(guard.position, Point::new(1, 2));
+
+        // Up to the obstacle, then out on the right
+        let states: Vec<Guard> = Walker::new(&lab, guard).states().collect();
+        let positions: Vec<(i64, i64)> = states
+            .iter()
+            .map(|g| (g.position.x, g.position.y))
+            .collect();
+        assert_eq!(positions, [(1, 2), (1, 1), (1, 1), (2, 1), (3, 1)]);
+        assert_eq!(states[2].heading, Direction::Right);
+        assert!(matches!(
+            Walker::new(&lab, guard).run(),
+            Outcome::Exit { steps: 4, .. }
+        ));
+    }
+}
@@ -1,5 +1,17 @@
 use std::fs;
 
+pub mod counter;
+pub mod export;
+pub mod extsort;
+pub mod gen;
+pub mod geometry;
+pub mod lab;
+pub mod parallel;
+pub mod render;
+pub mod stencil;
+pub mod walker;
+pub mod wordsearch;
+
 pub fn read_input_to_file(input_f: &str) -> fs::File {
     let input_f = std::path::Path::new(input_f);
     match std::fs::File::open(input_f) {
@@ -7,3 +19,15 @@ pub fn read_input_to_file(input_f: &str) -> fs::File {
         Err(e) => panic!("could not open file {}: {}!", input_f.display(), e),
     }
 }
+
+/// Is `name` (e.g. `--render`) one of the command line arguments?
+pub fn flag(name: &str) -> bool {
+    std::env::args().any(|a| a == name)
+}
+
+/// Value given after `name` on the command line, e.g. `--export out.png`
+pub fn option(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|a| a != name);
+    args.next()?;
+    args.next()
+}
@@ -1,2 +1,58 @@
-mod lib;
-fn main() {}
+// Runner for everything that is not a single day
+//
+// - `aoc2024 gen <day> --seed S --size N [--solvable P]` prints a random
+//   input for that day, always the same one for a given seed
+// - `aoc2024 <dayNN> [--threads N] [ARGS...]` runs that day's solution, the
+//   arguments are passed on as they are
+
+use aoc2024::{gen, option};
+use std::process::Command;
+
+fn usage() -> ! {
+    eprintln!("usage: aoc2024 gen <day> [--seed S] [--size N] [--solvable PERCENT]");
+    eprintln!("       aoc2024 <dayNN> [--threads N] [ARGS...]");
+    std::process::exit(2)
+}
+
+/// Run the `dayNN` binary installed next to this one
+fn run_day(day: &str, args: &[String]) -> ! {
+    let exe = std::env::current_exe().expect("could not find the runner executable");
+    let bin = exe.with_file_name(day);
+    let status = Command::new(&bin)
+        .args(args)
+        .status()
+        .unwrap_or_else(|e| panic!("could not run {}: {}", bin.display(), e));
+    std::process::exit(status.code().unwrap_or(1))
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("gen") => {
+            let day: u32 = match args.get(1) {
+                Some(day) => day
+                    .trim_start_matches("day")
+                    .parse()
+                    .unwrap_or_else(|_| usage()),
+                None => usage(),
+            };
+            let options = gen::Options {
+                seed: option("--seed").map_or(0, |s| s.parse().unwrap_or_else(|_| usage())),
+                size: option("--size").map_or(10, |s| s.parse().unwrap_or_else(|_| usage())),
+                solvable: option("--solvable")
+                    .map_or(50, |s| s.parse().unwrap_or_else(|_| usage())),
+            };
+            match gen::generate(day, &options) {
+                Some(input) => print!("{}", input),
+                None => {
+                    eprintln!("no generator for day {}", day);
+                    std::process::exit(1)
+                }
+            }
+        }
+        Some(day) if day.starts_with("day") && day[3..].parse::<u32>().is_ok() => {
+            run_day(day, &args[1..])
+        }
+        _ => usage(),
+    }
+}
@@ -0,0 +1,70 @@
+// Optional parallelism for independent work items
+//
+// - with the `parallel` feature, work is spread over a rayon thread pool
+// - without it, the same functions run sequentially
+// - results always come back in input order, so answers do not depend on it
+// - `--threads N` sets the size of the pool
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Size the thread pool from `--threads N`, call once at the start of `main`
+pub fn configure() {
+    let Some(threads) = crate::option("--threads") else {
+        return;
+    };
+    let threads: usize = threads.parse().expect("invalid --threads");
+    #[cfg(feature = "parallel")]
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()
+        .expect("thread pool already configured");
+    #[cfg(not(feature = "parallel"))]
+    if threads > 1 {
+        eprintln!("built without the `parallel` feature, running on 1 thread");
+    }
+}
+
+/// `f` applied to every item, in the order of `items`
+pub fn map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    return items.par_iter().map(f).collect();
+    #[cfg(not(feature = "parallel"))]
+    return items.iter().map(f).collect();
+}
+
+/// Items for which `f` is true, in the order of `items`
+pub fn filter<T, F>(items: &[T], f: F) -> Vec<&T>
+where
+    T: Sync,
+    F: Fn(&T) -> bool + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    return items.par_iter().filter(|item| f(item)).collect();
+    #[cfg(not(feature = "parallel"))]
+    return items.iter().filter(|item| f(item)).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_order() {
+        let items: Vec<u64> = (0..1000).collect();
+        assert_eq!(
+            map(&items, |x| x * 2),
+            (0..2000).step_by(2).collect::<Vec<_>>()
+        );
+        let odd: Vec<u64> = filter(&items, |x| x % 2 == 1)
+            .into_iter()
+            .copied()
+            .collect();
+        assert_eq!(odd, (1..1000).step_by(2).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,214 @@
+// Terminal rendering of character grids
+//
+// - draw the base grid
+// - draw overlays on top of it, later overlays win
+// - each overlay has a color, a label for the legend and optional glyphs
+// - colors are only used when stdout is a terminal
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, IsTerminal},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Grey,
+    /// Not a color, the terminal's faint style for text that matters less
+    Dim,
+}
+
+impl Color {
+    /// Colors to cycle through when there is one overlay per group
+    pub const CYCLE: [Color; 6] = [
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+    ];
+
+    fn ansi_code(&self) -> u8 {
+        match self {
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+            Color::White => 37,
+            Color::Grey => 90,
+            Color::Dim => 2,
+        }
+    }
+
+    /// Wrap `s` in the ANSI escape codes for this color
+    pub fn paint(&self, s: &str) -> String {
+        format!("\x1b[{}m{}\x1b[0m", self.ansi_code(), s)
+    }
+}
+
+/// Should output use ANSI colors?
+pub fn stdout_is_tty() -> bool {
+    io::stdout().is_terminal()
+}
+
+/// A cell highlighted by an overlay, drawn with `glyph` if set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mark {
+    pub row: usize,
+    pub col: usize,
+    pub glyph: Option<char>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Overlay {
+    pub label: String,
+    pub color: Color,
+    pub marks: Vec<Mark>,
+}
+
+impl Overlay {
+    pub fn new(label: impl Into<String>, color: Color) -> Self {
+        Overlay {
+            label: label.into(),
+            color,
+            marks: vec![],
+        }
+    }
+
+    /// Highlight cells, keeping the letter of the base grid
+    pub fn cells(mut self, cells: impl IntoIterator<Item = (usize, usize)>) -> Self {
+        self.marks.extend(cells.into_iter().map(|(row, col)| Mark {
+            row,
+            col,
+            glyph: None,
+        }));
+        self
+    }
+
+    /// Highlight cells, drawing them with their own glyph
+    pub fn glyphs(mut self, cells: impl IntoIterator<Item = ((usize, usize), char)>) -> Self {
+        self.marks
+            .extend(cells.into_iter().map(|((row, col), glyph)| Mark {
+                row,
+                col,
+                glyph: Some(glyph),
+            }));
+        self
+    }
+
+    /// Cells highlighted, a cell marked more than once counts once
+    pub fn distinct_cells(&self) -> usize {
+        let cells: HashSet<(usize, usize)> = self.marks.iter().map(|m| (m.row, m.col)).collect();
+        cells.len()
+    }
+}
+
+pub struct Renderer<'a> {
+    grid: &'a [Vec<char>],
+    overlays: Vec<Overlay>,
+    color: bool,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(grid: &'a [Vec<char>]) -> Self {
+        Renderer {
+            grid,
+            overlays: vec![],
+            color: stdout_is_tty(),
+        }
+    }
+
+    /// Force colors on or off, instead of detecting a terminal
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn overlay(mut self, overlay: Overlay) -> Self {
+        self.overlays.push(overlay);
+        self
+    }
+
+    pub fn overlays(mut self, overlays: impl IntoIterator<Item = Overlay>) -> Self {
+        self.overlays.extend(overlays);
+        self
+    }
+
+    /// The grid with every overlay applied, followed by the legend
+    pub fn render(&self) -> String {
+        // Topmost mark for each cell
+        let mut top: HashMap<(usize, usize), (&Overlay, &Mark)> = HashMap::new();
+        for overlay in &self.overlays {
+            for mark in &overlay.marks {
+                top.insert((mark.row, mark.col), (overlay, mark));
+            }
+        }
+
+        let mut out = String::new();
+        for (row, cells) in self.grid.iter().enumerate() {
+            for (col, &cell) in cells.iter().enumerate() {
+                match top.get(&(row, col)) {
+                    Some((overlay, mark)) => {
+                        let glyph = mark.glyph.unwrap_or(cell).to_string();
+                        if self.color {
+                            out += &overlay.color.paint(&glyph);
+                        } else {
+                            out += &glyph;
+                        }
+                    }
+                    None => out.push(cell),
+                }
+            }
+            out.push('\n');
+        }
+
+        for overlay in &self.overlays {
+            let swatch = if self.color {
+                overlay.color.paint("■")
+            } else {
+                format!("{:?}", overlay.color)
+            };
+            out += &format!(
+                "{} {} ({} cells)\n",
+                swatch,
+                overlay.label,
+                overlay.distinct_cells()
+            );
+        }
+        out
+    }
+
+    pub fn print(&self) {
+        print!("{}", self.render());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_overlays() {
+        let grid: Vec<Vec<char>> = vec!["ab".chars().collect(), "cd".chars().collect()];
+        let renderer = Renderer::new(&grid)
+            .color(false)
+            .overlay(Overlay::new("first", Color::Red).cells([(0, 0), (1, 1), (0, 0)]))
+            .overlay(Overlay::new("second", Color::Blue).glyphs([((1, 1), '#')]));
+        assert_eq!(
+            renderer.render(),
+            "ab\nc#\nRed first (2 cells)\nBlue second (1 cells)\n"
+        );
+
+        let colored = renderer.color(true).render();
+        assert!(colored.starts_with("\x1b[31ma\x1b[0mb\n"));
+    }
+}
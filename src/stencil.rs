@@ -0,0 +1,218 @@
+// Stencil pattern matching on character grids
+//
+// - a stencil is a small pattern, rows separated by `/`, `.` matches anything
+// - e.g. `M.S/.A./M.S` is an X-shaped MAS
+// - optionally match every rotation and/or reflection of the pattern
+// - each match reports its top-left position and the orientation used
+
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Stencil {
+    cells: Vec<Vec<Option<char>>>,
+}
+
+/// Quarter turns clockwise, applied after an optional left-right reflection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Orientation {
+    pub quarter_turns: u8,
+    pub reflected: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    /// Only the stencil as written
+    None,
+    /// The four rotations
+    Rotations,
+    /// The four rotations of the stencil and of its mirror image
+    All,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub row: usize,
+    pub col: usize,
+    pub orientation: Orientation,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseStencilError;
+
+impl fmt::Display for ParseStencilError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "stencil rows must be non-empty and of equal length")
+    }
+}
+
+impl FromStr for Stencil {
+    type Err = ParseStencilError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cells: Vec<Vec<Option<char>>> = s
+            .split('/')
+            .map(|row| row.chars().map(|c| (c != '.').then_some(c)).collect())
+            .collect();
+        let width = cells[0].len();
+        if width == 0 || cells.iter().any(|row| row.len() != width) {
+            return Err(ParseStencilError);
+        }
+        Ok(Stencil { cells })
+    }
+}
+
+impl Stencil {
+    pub fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    /// Offsets of the cells that are not wildcards
+    pub fn fixed_cells(&self) -> Vec<(usize, usize)> {
+        let mut fixed = vec![];
+        for (i, row) in self.cells.iter().enumerate() {
+            for (j, cell) in row.iter().enumerate() {
+                if cell.is_some() {
+                    fixed.push((i, j));
+                }
+            }
+        }
+        fixed
+    }
+
+    /// Rotate a quarter turn clockwise
+    pub fn rotate(&self) -> Stencil {
+        let (h, w) = (self.height(), self.width());
+        let cells = (0..w)
+            .map(|r| (0..h).map(|c| self.cells[h - 1 - c][r]).collect())
+            .collect();
+        Stencil { cells }
+    }
+
+    /// Mirror left to right
+    pub fn reflect(&self) -> Stencil {
+        let cells = self
+            .cells
+            .iter()
+            .map(|row| row.iter().rev().cloned().collect())
+            .collect();
+        Stencil { cells }
+    }
+
+    pub fn oriented(&self, orientation: Orientation) -> Stencil {
+        let base = if orientation.reflected {
+            self.reflect()
+        } else {
+            self.clone()
+        };
+        (0..orientation.quarter_turns).fold(base, |s, _| s.rotate())
+    }
+
+    /// Distinct variants of the stencil for the given symmetry
+    ///
+    /// Symmetric stencils produce fewer variants, so a shape is never counted
+    /// twice at the same position.
+    pub fn orientations(&self, symmetry: Symmetry) -> Vec<(Orientation, Stencil)> {
+        let reflections: &[bool] = match symmetry {
+            Symmetry::All => &[false, true],
+            _ => &[false],
+        };
+        let turns = match symmetry {
+            Symmetry::None => 1,
+            _ => 4,
+        };
+        let mut variants: Vec<(Orientation, Stencil)> = vec![];
+        for &reflected in reflections {
+            for quarter_turns in 0..turns {
+                let orientation = Orientation {
+                    quarter_turns,
+                    reflected,
+                };
+                let stencil = self.oriented(orientation);
+                if !variants.iter().any(|(_, s)| *s == stencil) {
+                    variants.push((orientation, stencil));
+                }
+            }
+        }
+        variants
+    }
+
+    /// Does the stencil match with its top-left corner at `(row, col)`?
+    pub fn matches_at(&self, grid: &[Vec<char>], row: usize, col: usize) -> bool {
+        self.cells.iter().enumerate().all(|(i, cells)| {
+            cells.iter().enumerate().all(|(j, cell)| {
+                let actual = grid.get(row + i).and_then(|r| r.get(col + j));
+                match (cell, actual) {
+                    (_, None) => false,
+                    (None, Some(_)) => true,
+                    (Some(expected), Some(actual)) => expected == actual,
+                }
+            })
+        })
+    }
+
+    /// Every match of the stencil in any orientation allowed by `symmetry`
+    pub fn find(&self, grid: &[Vec<char>], symmetry: Symmetry) -> Vec<Match> {
+        let variants = self.orientations(symmetry);
+        let mut matches = vec![];
+        for (row, cells) in grid.iter().enumerate() {
+            for col in 0..cells.len() {
+                for (orientation, stencil) in &variants {
+                    if stencil.matches_at(grid, row, col) {
+                        matches.push(Match {
+                            row,
+                            col,
+                            orientation: *orientation,
+                        });
+                    }
+                }
+            }
+        }
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(s: &str) -> Vec<Vec<char>> {
+        s.lines().map(|l| l.chars().collect()).collect()
+    }
+
+    #[test]
+    fn test_orientations() {
+        let x_mas: Stencil = "M.S/.A./M.S".parse().unwrap();
+        assert_eq!(x_mas.orientations(Symmetry::None).len(), 1);
+        assert_eq!(x_mas.orientations(Symmetry::Rotations).len(), 4);
+        // Mirror images of the X are already rotations of it
+        assert_eq!(x_mas.orientations(Symmetry::All).len(), 4);
+
+        let l: Stencil = "A./AB".parse().unwrap();
+        assert_eq!(l.rotate(), "AA/B.".parse().unwrap());
+        assert_eq!(l.fixed_cells(), vec![(0, 0), (1, 0), (1, 1)]);
+        assert_eq!(l.orientations(Symmetry::Rotations).len(), 4);
+        assert_eq!(l.orientations(Symmetry::All).len(), 8);
+        assert_eq!("".parse::<Stencil>(), Err(ParseStencilError));
+        assert_eq!("AB/C".parse::<Stencil>(), Err(ParseStencilError));
+    }
+
+    #[test]
+    fn test_find() {
+        let g = grid("MXS\nXAX\nMXS\nSXM");
+        let x_mas: Stencil = "M.S/.A./M.S".parse().unwrap();
+        let found = x_mas.find(&g, Symmetry::Rotations);
+        assert_eq!(
+            found,
+            vec![Match {
+                row: 0,
+                col: 0,
+                orientation: Orientation::default()
+            }]
+        );
+        assert!(x_mas.find(&g[1..], Symmetry::Rotations).is_empty());
+    }
+}
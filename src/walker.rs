@@ -0,0 +1,210 @@
+// Grid walker simulation
+//
+// - an agent has a position and a heading
+// - a pluggable step rule moves the agent, or lets it leave the world
+// - walking ends either by exiting or by repeating a state (a loop)
+// - loops are found by hashing visited states or with Brent's algorithm
+
+use crate::geometry::Point;
+use std::{collections::HashMap, hash::Hash};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Agent<H> {
+    pub position: Point,
+    pub heading: H,
+}
+
+impl<H> Agent<H> {
+    pub fn new(position: Point, heading: H) -> Self {
+        Agent { position, heading }
+    }
+}
+
+pub trait StepRule<H> {
+    /// State of the agent after one step, `None` once it leaves the world
+    fn step(&self, agent: &Agent<H>) -> Option<Agent<H>>;
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome<H> {
+    /// The agent left after taking `steps` steps, `last` is its final state
+    Exit { steps: usize, last: Agent<H> },
+    /// The agent repeats forever: `entry` is first reached after `start`
+    /// steps and comes back every `period` steps
+    Loop {
+        entry: Agent<H>,
+        start: usize,
+        period: usize,
+    },
+}
+
+impl<H> Outcome<H> {
+    pub fn is_loop(&self) -> bool {
+        matches!(self, Outcome::Loop { .. })
+    }
+}
+
+pub struct Walker<'a, R, H> {
+    rule: &'a R,
+    start: Agent<H>,
+}
+
+impl<'a, R, H> Walker<'a, R, H>
+where
+    R: StepRule<H>,
+    H: Clone + Eq + Hash,
+{
+    pub fn new(rule: &'a R, start: Agent<H>) -> Self {
+        Walker { rule, start }
+    }
+
+    /// Every state of the walk, starting with the initial one
+    ///
+    /// Never ends if the agent is stuck in a loop.
+    pub fn states(&self) -> States<'a, R, H> {
+        States {
+            rule: self.rule,
+            next: Some(self.start.clone()),
+        }
+    }
+
+    /// Walk until exit or loop, remembering every state seen
+    pub fn run(&self) -> Outcome<H> {
+        let mut seen: HashMap<Agent<H>, usize> = HashMap::new();
+        let mut last = self.start.clone();
+        for (i, state) in self.states().enumerate() {
+            if let Some(&start) = seen.get(&state) {
+                return Outcome::Loop {
+                    entry: state,
+                    start,
+                    period: i - start,
+                };
+            }
+            seen.insert(state.clone(), i);
+            last = state;
+        }
+        Outcome::Exit {
+            steps: seen.len() - 1,
+            last,
+        }
+    }
+
+    /// Walk until exit or loop with Brent's algorithm, in constant memory
+    pub fn run_brent(&self) -> Outcome<H> {
+        let step = |agent: &Agent<H>| self.rule.step(agent);
+
+        // Find the period: the hare runs ahead, the tortoise teleports to it
+        // at every power of two
+        let (mut power, mut period) = (1, 1);
+        let mut steps = 0;
+        let mut tortoise = self.start.clone();
+        let mut hare = match step(&tortoise) {
+            Some(next) => next,
+            None => {
+                return Outcome::Exit {
+                    steps,
+                    last: tortoise,
+                }
+            }
+        };
+        steps += 1;
+        while tortoise != hare {
+            if power == period {
+                tortoise = hare.clone();
+                power *= 2;
+                period = 0;
+            }
+            hare = match step(&hare) {
+                Some(next) => next,
+                None => return Outcome::Exit { steps, last: hare },
+            };
+            steps += 1;
+            period += 1;
+        }
+
+        // Find the start of the loop: keep both `period` steps apart
+        let advance = |agent: Agent<H>| step(&agent).expect("a looping walk never exits");
+        let mut tortoise = self.start.clone();
+        let mut hare = (0..period).fold(self.start.clone(), |a, _| advance(a));
+        let mut start = 0;
+        while tortoise != hare {
+            tortoise = advance(tortoise);
+            hare = advance(hare);
+            start += 1;
+        }
+        Outcome::Loop {
+            entry: tortoise,
+            start,
+            period,
+        }
+    }
+}
+
+pub struct States<'a, R, H> {
+    rule: &'a R,
+    next: Option<Agent<H>>,
+}
+
+impl<R, H> Iterator for States<'_, R, H>
+where
+    R: StepRule<H>,
+    H: Clone,
+{
+    type Item = Agent<H>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+        self.next = self.rule.step(&current);
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Walk right along a line of `len` cells, teleporting from `jump.0` back to `jump.1`
+    struct Line {
+        len: i64,
+        jump: Option<(i64, i64)>,
+    }
+
+    impl StepRule<()> for Line {
+        fn step(&self, agent: &Agent<()>) -> Option<Agent<()>> {
+            let x = match self.jump {
+                Some((from, to)) if agent.position.x == from => to,
+                _ => agent.position.x + 1,
+            };
+            (x < self.len).then(|| Agent::new(Point::new(x, 0), ()))
+        }
+    }
+
+    #[test]
+    fn test_exit() {
+        let line = Line { len: 5, jump: None };
+        let walker = Walker::new(&line, Agent::new(Point::new(0, 0), ()));
+        assert_eq!(walker.states().count(), 5);
+        let expected = Outcome::Exit {
+            steps: 4,
+            last: Agent::new(Point::new(4, 0), ()),
+        };
+        assert_eq!(walker.run(), expected);
+        assert_eq!(walker.run_brent(), expected);
+    }
+
+    #[test]
+    fn test_loop() {
+        let line = Line {
+            len: 10,
+            jump: Some((6, 2)),
+        };
+        let walker = Walker::new(&line, Agent::new(Point::new(0, 0), ()));
+        let expected = Outcome::Loop {
+            entry: Agent::new(Point::new(2, 0), ()),
+            start: 2,
+            period: 5,
+        };
+        assert_eq!(walker.run(), expected);
+        assert_eq!(walker.run_brent(), expected);
+    }
+}
@@ -0,0 +1,200 @@
+// Directional word search
+//
+// - every row, column and diagonal of the grid is read once as a line
+// - all dictionary words are searched at the same time with Aho-Corasick
+// - each line is also searched backwards, which covers the 4 reverse directions
+// - overlapping hits are all reported
+
+use aho_corasick::AhoCorasick;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Right,
+    Left,
+    Down,
+    Up,
+    DownRight,
+    UpLeft,
+    DownLeft,
+    UpRight,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::Right,
+        Direction::Left,
+        Direction::Down,
+        Direction::Up,
+        Direction::DownRight,
+        Direction::UpLeft,
+        Direction::DownLeft,
+        Direction::UpRight,
+    ];
+
+    /// (row change, col change)
+    pub fn offset(&self) -> (isize, isize) {
+        match self {
+            Direction::Right => (0, 1),
+            Direction::Left => (0, -1),
+            Direction::Down => (1, 0),
+            Direction::Up => (-1, 0),
+            Direction::DownRight => (1, 1),
+            Direction::UpLeft => (-1, -1),
+            Direction::DownLeft => (1, -1),
+            Direction::UpRight => (-1, 1),
+        }
+    }
+
+    pub fn reverse(&self) -> Direction {
+        match self {
+            Direction::Right => Direction::Left,
+            Direction::Left => Direction::Right,
+            Direction::Down => Direction::Up,
+            Direction::Up => Direction::Down,
+            Direction::DownRight => Direction::UpLeft,
+            Direction::UpLeft => Direction::DownRight,
+            Direction::DownLeft => Direction::UpRight,
+            Direction::UpRight => Direction::DownLeft,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Hit<'a> {
+    pub row: usize,
+    pub col: usize,
+    pub direction: Direction,
+    pub word: &'a str,
+}
+
+pub struct WordSearch {
+    words: Vec<String>,
+    automaton: AhoCorasick,
+}
+
+impl WordSearch {
+    /// Search for `words`, empty words are left out since they are not in
+    /// any cell
+    pub fn new<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let words: Vec<String> = words
+            .into_iter()
+            .map(Into::into)
+            .filter(|w: &String| !w.is_empty())
+            .collect();
+        let automaton = AhoCorasick::new(&words).expect("word list fits in an automaton");
+        WordSearch { words, automaton }
+    }
+
+    /// Every occurrence of every word, in all 8 directions
+    ///
+    /// A word that reads the same backwards (or a single letter) is reported
+    /// once per direction it can be read in.
+    pub fn find(&self, grid: &[Vec<char>]) -> Vec<Hit<'_>> {
+        let mut hits = vec![];
+        for direction in [
+            Direction::Right,
+            Direction::Down,
+            Direction::DownRight,
+            Direction::DownLeft,
+        ] {
+            for mut line in lines(grid, direction) {
+                self.search_line(grid, &line, direction, &mut hits);
+                line.reverse();
+                self.search_line(grid, &line, direction.reverse(), &mut hits);
+            }
+        }
+        hits
+    }
+
+    fn search_line<'a>(
+        &'a self,
+        grid: &[Vec<char>],
+        line: &[(usize, usize)],
+        direction: Direction,
+        hits: &mut Vec<Hit<'a>>,
+    ) {
+        // Byte offset in the line text -> position in the line
+        let mut text = String::new();
+        let mut index_of_byte = vec![];
+        for (i, &(row, col)) in line.iter().enumerate() {
+            let c = grid[row][col];
+            text.push(c);
+            index_of_byte.extend(std::iter::repeat_n(i, c.len_utf8()));
+        }
+
+        for m in self.automaton.find_overlapping_iter(&text) {
+            let (row, col) = line[index_of_byte[m.start()]];
+            hits.push(Hit {
+                row,
+                col,
+                direction,
+                word: &self.words[m.pattern().as_usize()],
+            });
+        }
+    }
+}
+
+/// Every maximal line of cells going in `direction`
+fn lines(grid: &[Vec<char>], direction: Direction) -> Vec<Vec<(usize, usize)>> {
+    let (dr, dc) = direction.offset();
+    let cell = |row: isize, col: isize| -> Option<(usize, usize)> {
+        let (row, col) = (usize::try_from(row).ok()?, usize::try_from(col).ok()?);
+        grid.get(row)?.get(col).map(|_| (row, col))
+    };
+
+    let mut lines = vec![];
+    for (row, cells) in grid.iter().enumerate() {
+        for col in 0..cells.len() {
+            let (r, c) = (row as isize, col as isize);
+            // Only start from cells with nothing before them
+            if cell(r - dr, c - dc).is_some() {
+                continue;
+            }
+            let line = (0..).map_while(|k| cell(r + k * dr, c + k * dc)).collect();
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_all_directions() {
+        let grid: Vec<Vec<char>> = ["CAT.", "A..A", "TACT"]
+            .iter()
+            .map(|l| l.chars().collect())
+            .collect();
+        let search = WordSearch::new(["CAT", "ACT", "TAC"]);
+        let mut hits = search.find(&grid);
+        hits.sort_by_key(|h| (h.row, h.col, h.word));
+
+        let expected = [
+            (0, 0, Direction::Right, "CAT"),
+            (0, 0, Direction::Down, "CAT"),
+            (0, 2, Direction::Left, "TAC"),
+            (2, 0, Direction::Right, "TAC"),
+            (2, 0, Direction::Up, "TAC"),
+            (2, 1, Direction::Right, "ACT"),
+            (2, 2, Direction::Left, "CAT"),
+        ];
+        let hits: Vec<_> = hits
+            .iter()
+            .map(|h| (h.row, h.col, h.direction, h.word))
+            .collect();
+        assert_eq!(hits, expected);
+    }
+
+    #[test]
+    fn test_empty_word() {
+        let grid = [vec!['A', 'B']];
+        assert!(WordSearch::new([""]).find(&grid).is_empty());
+        assert_eq!(WordSearch::new(["", "B"]).find(&grid).len(), 8);
+    }
+}